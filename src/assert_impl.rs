@@ -1,22 +1,84 @@
-use proc_macro2::TokenStream;
-use quote::{ToTokens, quote, quote_spanned};
+use std::collections::HashSet;
+
+use proc_macro2::{Delimiter, Group, Ident, Punct, Spacing, Span, TokenStream, TokenTree};
+use quote::{ToTokens, format_ident, quote, quote_spanned};
 
 use crate::{
-    parse::parse_predicate_list,
-    types::{GenericParam, Predicate, PredicateTree},
+    descriptors::push_descriptors,
+    docs::guarantees_doc,
+    hygiene::Names,
+    lint::lint_trees,
+    parse::parse_input,
+    pretty::{pretty, words},
+    substitute::{Rewrite, Substitution},
+    types::{Error, GenericParam, Predicate, PredicateTree, TreeSource, Warning},
 };
 
+/// The length after which the words of a source are left out of the name of
+/// its test function.
+const MAX_TEST_NAME_LEN: usize = 64;
+
 pub fn main(tokens: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    let tokens = TokenStream::from(tokens);
+
     let mut result = TokenStream::new();
     let mut errors = Vec::new();
 
+    let mut names = Names::new(&tokens);
+    let helpers = Helpers::new(&mut names);
+
+    let (config, mut trees, sources) = parse_input(tokens, &mut errors);
+    remove_concrete_conditions(&mut trees, &mut Vec::new());
+    let warnings = lint_trees(&trees, &config);
+
+    check_witnesses(&trees, &mut Vec::new(), &mut errors);
+
+    if let Some((vis, module_name)) = &config.descriptors {
+        push_descriptors(&trees, vis, module_name, &mut result);
+    }
+
+    if let Some(docs_macro) = &config.docs_macro {
+        let doc = guarantees_doc(&trees);
+
+        result.extend(quote! {
+            macro_rules! #docs_macro {
+                () => {
+                    #doc
+                };
+            }
+        });
+    }
+
+    rename_shadowing_params(&mut trees, &mut Vec::new(), &mut names);
+    replace_placeholders(&mut trees, &mut names);
+
+    if config.emit_tests {
+        push_tests(&trees, &sources, config.test_module.as_ref(), &mut result);
+    }
+
+    let explanation = config.explain.then(|| explain(&trees));
+
     push_trees(
-        parse_predicate_list(tokens.into(), &mut errors),
+        trees,
         Vec::new(),
         &mut Vec::new(),
+        &mut Vec::new(),
+        &helpers,
         &mut result,
     );
 
+    if let Some(mut explanation) = explanation {
+        if config.explain_tokens {
+            explanation.push_str(&format!("\n\ngenerated code:\n{result}"));
+        }
+
+        Warning::new_at_span(Span::call_site(), explanation).to_tokens(&mut result);
+    }
+
+    for warning in warnings {
+        warning.to_tokens(&mut result);
+    }
+
     for error in errors {
         error.to_tokens(&mut result);
     }
@@ -24,73 +86,531 @@ pub fn main(tokens: proc_macro::TokenStream) -> proc_macro::TokenStream {
     result.into()
 }
 
+/// The identifiers of the items and generic parameters introduced by the
+/// generated code.
+struct Helpers {
+    context_fn: Ident,
+    check_fn: Ident,
+    assert_ty: Ident,
+    reason_trait: Ident,
+}
+
+impl Helpers {
+    fn new(names: &mut Names) -> Self {
+        Self {
+            context_fn: names.fresh_ident("_context"),
+            check_fn: names.fresh_ident("_check"),
+            assert_ty: names.fresh_prefix_ident("_AssertTy"),
+            reason_trait: names.fresh_ident("_Reason"),
+        }
+    }
+}
+
+/// Removes the where-predicates of groups marked `unconditional_if_concrete`
+/// that don't mention a generic parameter of their context.
+fn remove_concrete_conditions(trees: &mut [PredicateTree], scope: &mut Vec<GenericParam>) {
+    for tree in trees {
+        let PredicateTree::Group(group) = tree else {
+            continue;
+        };
+
+        let original_scope_len = scope.len();
+        scope.extend(group.generic_params.iter().cloned());
+
+        if group.unconditional_if_concrete {
+            group.where_predicates.retain(|predicate| {
+                let tokens = predicate.to_token_stream();
+                scope.iter().any(|param| param.is_used_in(&tokens))
+            });
+        }
+
+        remove_concrete_conditions(&mut group.predicates, scope);
+
+        scope.truncate(original_scope_len);
+    }
+}
+
+/// Reports witnesses that can never be used because another parameter of
+/// their context has no witness.
+fn check_witnesses<'a>(
+    trees: &'a [PredicateTree],
+    scope: &mut Vec<&'a GenericParam>,
+    errors: &mut Vec<Error>,
+) {
+    for tree in trees {
+        let PredicateTree::Group(group) = tree else {
+            continue;
+        };
+
+        let original_scope_len = scope.len();
+        scope.extend(&group.generic_params);
+
+        if let Some(witnessed) = group.generic_params.iter().find(|p| p.witness.is_some())
+            && let Some(missing) = scope.iter().find(|p| !p.is_lifetime && p.witness.is_none())
+        {
+            errors.push(Error::new_at_span(
+                witnessed.span,
+                format!(
+                    "`{}` has a witness but `{}` doesn't, so the context can't be instantiated",
+                    witnessed.inline_arg, missing.inline_arg,
+                ),
+            ));
+        }
+
+        check_witnesses(&group.predicates, scope, errors);
+
+        scope.truncate(original_scope_len);
+    }
+}
+
+/// Replaces the `_` placeholders in the bound of each predicate with fresh
+/// type parameters, and records them in `inferred_params`.
+fn replace_placeholders(trees: &mut [PredicateTree], names: &mut Names) {
+    for tree in trees {
+        match tree {
+            PredicateTree::Group(group) => replace_placeholders(&mut group.predicates, names),
+            PredicateTree::Predicate(predicate) => {
+                let mut inferred_params = Vec::new();
+
+                predicate.bound =
+                    replace_placeholder_tokens(&predicate.bound, &mut inferred_params, names);
+
+                predicate.inferred_params = inferred_params;
+            }
+        }
+    }
+}
+
+fn replace_placeholder_tokens(
+    tokens: &TokenStream,
+    inferred_params: &mut Vec<Ident>,
+    names: &mut Names,
+) -> TokenStream {
+    let mut result = TokenStream::new();
+    let mut is_after_lifetime_prefix = false;
+
+    for token in tokens.clone() {
+        let is_lifetime_prefix =
+            matches!(&token, TokenTree::Punct(punct) if punct.as_char() == '\'');
+
+        match token {
+            TokenTree::Ident(ident) if ident == "_" && !is_after_lifetime_prefix => {
+                let base = format!("_Inferred{}", inferred_params.len());
+                let param = Ident::new(&names.fresh(&base), ident.span());
+                result.extend([TokenTree::Ident(param.clone())]);
+                inferred_params.push(param);
+            }
+            TokenTree::Group(group) => {
+                let mut new_group = Group::new(
+                    group.delimiter(),
+                    replace_placeholder_tokens(&group.stream(), inferred_params, names),
+                );
+                new_group.set_span(group.span());
+
+                result.extend([TokenTree::Group(new_group)]);
+            }
+            token => result.extend([token]),
+        }
+
+        is_after_lifetime_prefix = is_lifetime_prefix;
+    }
+
+    result
+}
+
+/// Renames the generic parameters that shadow a parameter of an outer group,
+/// from `T` to `T_shadowing`, or from `N` to `N_SHADOWING` for const
+/// parameters.
+///
+/// Nested groups are flattened into a single generic context, so an inner
+/// `for<T>` inside an outer `for<T>` would otherwise declare `T` twice. The
+/// new name shows up in errors and explanations, so it says where it comes
+/// from.
+fn rename_shadowing_params(
+    trees: &mut [PredicateTree],
+    scope: &mut Vec<GenericParam>,
+    names: &mut Names,
+) {
+    for tree in trees {
+        let PredicateTree::Group(group) = tree else {
+            continue;
+        };
+
+        for i in 0..group.generic_params.len() {
+            let param = &group.generic_params[i];
+            if !scope.iter().any(|outer| outer.shares_name_with(param)) {
+                continue;
+            }
+
+            let is_const = matches!(
+                param.tokens.clone().into_iter().next(),
+                Some(TokenTree::Ident(ident)) if ident == "const"
+            );
+            let suffix = if is_const { "_SHADOWING" } else { "_shadowing" };
+            let new_name = names.fresh(&format!("{}{suffix}", param.name));
+            let new_name = Ident::new(&new_name, param.name.span());
+
+            let replacement = if param.is_lifetime {
+                let lifetime_prefix = Punct::new('\'', Spacing::Joint);
+                quote! { #lifetime_prefix #new_name }
+            } else {
+                quote! { #new_name }
+            };
+
+            let substitution = Substitution {
+                name: param.name.clone(),
+                is_lifetime: param.is_lifetime,
+                replacement,
+            };
+
+            substitution.apply_to_group_contents(group);
+            group.generic_params[i].name = new_name;
+        }
+
+        let original_scope_len = scope.len();
+        scope.extend(group.generic_params.iter().cloned());
+
+        rename_shadowing_params(&mut group.predicates, scope, names);
+
+        scope.truncate(original_scope_len);
+    }
+}
+
+/// Returns the predicates as they are asserted by the generated code, after
+/// nested groups are merged, parameters are renamed and placeholders are
+/// replaced.
+fn explain(trees: &[PredicateTree]) -> String {
+    let mut result = String::from("assert_impl! asserts:");
+
+    for predicate in PredicateTree::flatten(trees) {
+        result.push_str("\n    ");
+
+        for attr in &predicate.attrs {
+            result.push_str(&pretty(attr));
+            result.push(' ');
+        }
+
+        result.push_str(&predicate.text());
+    }
+
+    result
+}
+
+/// Emits an empty `#[test]` function for each top-level tree, named after its
+/// label or the words of its source, so that test runners list the
+/// assertions. The assertions are still checked at compile time by the code
+/// emitted by `push_trees`.
+///
+/// With `#![test(mod name)]`, the functions are put in a module with that
+/// name, so that invocations with the same predicates in one module, like the
+/// instances of a template, don't define the same function twice.
+fn push_tests(
+    trees: &[PredicateTree],
+    sources: &[TreeSource],
+    module: Option<&Ident>,
+    tokens: &mut TokenStream,
+) {
+    let mut names = Names::new(&TokenStream::new());
+    let mut tests = TokenStream::new();
+
+    for (tree, source) in trees.iter().zip(sources) {
+        let (attrs, span) = match tree {
+            PredicateTree::Group(group) => (&group.attrs[..], group.span),
+            PredicateTree::Predicate(predicate) => (&[][..], predicate.span),
+        };
+
+        let name = match source {
+            TreeSource::Label(label) => label.to_string(),
+            TreeSource::Tokens(source) => {
+                let mut name = String::from("assert_impl");
+                for word in words(source) {
+                    if name.len() + word.len() >= MAX_TEST_NAME_LEN {
+                        break;
+                    }
+
+                    name.push('_');
+                    name.push_str(&word);
+                }
+
+                name
+            }
+        };
+
+        let name = Ident::new(&names.fresh(&name), span);
+
+        tests.extend(quote! {
+            #(#attrs)*
+            #[test]
+            fn #name() {}
+        });
+    }
+
+    match module {
+        Some(module) => tokens.extend(quote! {
+            #[cfg(test)]
+            mod #module {
+                #tests
+            }
+        }),
+        None => tokens.extend(tests),
+    }
+}
+
 fn push_trees(
     trees: Vec<PredicateTree>,
     mut generic_params: Vec<&GenericParam>,
     where_predicates: &mut Vec<Predicate>,
+    attrs: &mut Vec<TokenStream>,
+    helpers: &Helpers,
     tokens: &mut TokenStream,
 ) {
-    let predicates = push_groups(trees, &generic_params, where_predicates, tokens);
+    let predicates = push_groups(
+        trees,
+        &generic_params,
+        where_predicates,
+        attrs,
+        helpers,
+        tokens,
+    );
+
+    let Helpers {
+        context_fn,
+        check_fn,
+        assert_ty,
+        reason_trait,
+    } = helpers;
 
     generic_params.sort_by(|a, b| a.list_cmp(b));
 
+    // Witnesses prove that the context's where-clause can be satisfied, so
+    // the context is only instantiated if every parameter has one. Lifetimes
+    // are left out of the generic arguments to be inferred.
+    let instantiation = generic_params
+        .iter()
+        .find(|p| p.witness.is_some())
+        .and_then(|witnessed| {
+            let witness_args = generic_params
+                .iter()
+                .filter(|p| !p.is_lifetime)
+                .map(|p| p.witness.as_ref())
+                .collect::<Option<Vec<_>>>()?;
+
+            Some(quote_spanned! {
+                witnessed.span =>
+                const _: fn() = #context_fn::<#(#witness_args),*>;
+            })
+        });
+
+    if predicates.is_empty() && instantiation.is_none() {
+        return;
+    }
+
+    let context = Context {
+        generic_params: &generic_params,
+        where_predicates,
+        helpers,
+    };
+
     let inline_generic_args = generic_params
         .iter()
         .map(|p| &p.inline_arg)
         .collect::<Vec<_>>();
-
-    let generic_marker_types = generic_params
+    let lifetime_args = generic_params
         .iter()
-        .map(|p| &p.marker_type)
+        .filter(|p| p.is_lifetime)
+        .map(|p| &p.inline_arg)
         .collect::<Vec<_>>();
 
     let mut context_tokens = TokenStream::new();
+    let mut plain_predicates = Vec::<Predicate>::new();
+    let mut plain_texts = HashSet::new();
 
-    for predicate in predicates {
-        let left_side = predicate.left_side;
-        let bound = predicate.bound;
+    for mut predicate in predicates {
+        // Errors name the function whose bound isn't satisfied in "required
+        // by a bound in ...", so labelled predicates are checked by a function
+        // named after their label.
+        let check_fn = predicate.label.clone().unwrap_or_else(|| check_fn.clone());
+        let is_annotated = predicate.label.is_some() || predicate.reason.is_some();
 
-        let optional_unsized = if predicate.unsized_left_side {
-            quote! { ?Sized + }
-        } else {
-            TokenStream::new()
-        };
+        // Custom diagnostics can only be attached to traits, so a predicate
+        // with a reason is first asserted through a trait that is implemented
+        // for exactly the types that satisfy the predicate. The compiler
+        // suppresses that error if the plain assertion comes first, and the
+        // plain assertion still reports which part of the type doesn't
+        // satisfy the bound.
+        if let Some(reason) = predicate.reason.take() {
+            let bound = &predicate.bound;
 
-        context_tokens.extend(quote_spanned! {
-            predicate.span => {
-                _HelperTy::<#(#inline_generic_args,)* #left_side>(
-                    #(
-                        ::core::marker::PhantomData::<#generic_marker_types>,
-                    )*
-                    ::core::marker::PhantomData,
-                );
+            // The placeholders of the bound are parameters of the trait, so
+            // that the impl declares them and the check infers them.
+            let inferred_params = &predicate.inferred_params;
+            let inferred_param_decls = inferred_params
+                .iter()
+                .map(|param| quote! { #param: #(#lifetime_args +)* ?Sized })
+                .collect::<Vec<_>>();
 
-                struct _HelperTy<#(#generic_params,)* _AssertTy: #optional_unsized #bound>(
-                    #(
-                        ::core::marker::PhantomData<#generic_marker_types>,
-                    )*
-                    ::core::marker::PhantomData<_AssertTy>,
-                )
-                where
-                    #(#where_predicates),*;
-            }
-        });
+            // The message shows the placeholders as written. Braces in the
+            // message are format arguments like `{Self}`.
+            let written_bound = inferred_params.iter().fold(bound.clone(), |bound, param| {
+                Substitution {
+                    name: param.clone(),
+                    is_lifetime: false,
+                    replacement: quote! { _ },
+                }
+                .apply_to_tokens(&bound)
+            });
+            let bound_str = written_bound
+                .to_string()
+                .replace('{', "{{")
+                .replace('}', "}}");
+            let message = format!("`{{Self}}` doesn't implement `{bound_str}`");
+
+            // The compiler also hides errors at the span of an earlier error,
+            // so the left side is given the span of the predicate.
+            let mut left_side = Group::new(Delimiter::None, predicate.left_side.clone());
+            left_side.set_span(predicate.span);
+
+            let reason_predicate = Predicate {
+                left_side: left_side.into_token_stream(),
+                bound: quote! {
+                    #reason_trait<#(#inline_generic_args,)* #(#inferred_params),*>
+                },
+                unsized_left_side: true,
+                ..predicate.clone()
+            };
+
+            let check = context.check(&check_fn, &[reason_predicate], predicate.span);
+
+            context_tokens.extend(quote_spanned! {
+                predicate.span => {
+                    #[diagnostic::on_unimplemented(message = #message, note = #reason)]
+                    trait #reason_trait<#(#generic_params,)* #(#inferred_param_decls),*> {}
+
+                    #[diagnostic::do_not_recommend]
+                    impl<
+                        #(#generic_params,)*
+                        #(#inferred_param_decls,)*
+                        #assert_ty: ?Sized + #bound
+                    > #reason_trait<#(#inline_generic_args,)* #(#inferred_params),*> for #assert_ty
+                    where
+                        #(#where_predicates),*
+                    {
+                    }
+
+                    #check
+                }
+            });
+        }
+
+        // Predicates with a label or a reason are checked on their own, so
+        // that errors point at them. The others are checked together, and
+        // identical predicates only once.
+        if is_annotated {
+            let check = context.check(&check_fn, std::slice::from_ref(&predicate), predicate.span);
+            context_tokens.extend(quote! { { #check } });
+        } else if plain_texts.insert(predicate.to_token_stream().to_string()) {
+            plain_predicates.push(predicate);
+        }
+    }
+
+    if !plain_predicates.is_empty() {
+        context_tokens.extend(context.check(check_fn, &plain_predicates, Span::call_site()));
     }
 
     tokens.extend(quote! {
-        #[allow(clippy::all)]
+        #(#attrs)*
+        #[allow(clippy::all, non_camel_case_types, non_snake_case, unused_braces)]
         const _: () = {
-            fn _context<#(#generic_params),*>() where #(#where_predicates),* {
+            fn #context_fn<#(#generic_params),*>() where #(#where_predicates),* {
                 #context_tokens
             }
+
+            #instantiation
         };
     });
 }
 
+/// The generic parameters and where-clause of a context.
+struct Context<'a> {
+    generic_params: &'a [&'a GenericParam],
+    where_predicates: &'a [Predicate],
+    helpers: &'a Helpers,
+}
+
+impl Context<'_> {
+    /// Returns a function named `name` with a type parameter for each
+    /// predicate, bounded by the predicate, and a call to it from the context
+    /// with the left sides of the predicates, which only compiles if the
+    /// predicates hold in the context.
+    ///
+    /// The context's lifetimes are passed explicitly, so that lifetime bounds
+    /// are checked for every lifetime instead of for ones the compiler picks.
+    /// `'a: 'a` in the where-clause keeps them early-bound, which explicit
+    /// arguments require. The types that replaced `_` placeholders are left
+    /// out of the call to be inferred, and outlive every lifetime so that
+    /// placeholders like `&'a _` are valid.
+    fn check(&self, name: &Ident, predicates: &[Predicate], span: Span) -> TokenStream {
+        let generic_params = self.generic_params;
+        let where_predicates = self.where_predicates;
+        let assert_ty = &self.helpers.assert_ty;
+
+        // Labels are written by the user, but the function must not be
+        // visible to the user's code.
+        let mut name = name.clone();
+        name.set_span(Span::mixed_site().located_at(span));
+
+        let lifetime_args = generic_params
+            .iter()
+            .filter(|p| p.is_lifetime)
+            .map(|p| &p.inline_arg)
+            .collect::<Vec<_>>();
+
+        let generic_args = generic_params.iter().map(|p| &p.inline_arg);
+
+        let inferred_params = predicates
+            .iter()
+            .flat_map(|predicate| &predicate.inferred_params)
+            .collect::<Vec<_>>();
+        let inferred_param_decls = inferred_params
+            .iter()
+            .map(|param| quote! { #param: #(#lifetime_args +)* });
+        let placeholders = inferred_params
+            .iter()
+            .map(|param| quote_spanned! { param.span() => _ });
+
+        let assert_params = predicates.iter().enumerate().map(|(i, predicate)| {
+            let param = format_ident!("{assert_ty}{i}", span = assert_ty.span());
+            let bound = &predicate.bound;
+
+            if predicate.unsized_left_side {
+                quote! { #param: ?Sized + #bound }
+            } else {
+                quote! { #param: #bound }
+            }
+        });
+
+        let left_sides = predicates.iter().map(|predicate| &predicate.left_side);
+
+        quote_spanned! {
+            span =>
+            fn #name<#(#generic_params,)* #(#inferred_param_decls,)* #(#assert_params),*>()
+            where
+                #(#lifetime_args: #lifetime_args,)*
+                #(#where_predicates),*
+            {
+            }
+
+            #name::<#(#generic_args,)* #(#placeholders,)* #(#left_sides),*>();
+        }
+    }
+}
+
 fn push_groups(
     trees: Vec<PredicateTree>,
     generic_params: &Vec<&GenericParam>,
     where_predicates: &mut Vec<Predicate>,
+    attrs: &mut Vec<TokenStream>,
+    helpers: &Helpers,
     tokens: &mut TokenStream,
 ) -> Vec<Predicate> {
     let mut predicates = Vec::with_capacity(trees.len());
@@ -113,9 +633,20 @@ fn push_groups(
         let original_where_predicate_count = where_predicates.len();
         where_predicates.extend(group.where_predicates);
 
-        push_trees(group.predicates, generic_params, where_predicates, tokens);
+        let original_attr_count = attrs.len();
+        attrs.extend(group.attrs);
+
+        push_trees(
+            group.predicates,
+            generic_params,
+            where_predicates,
+            attrs,
+            helpers,
+            tokens,
+        );
 
         where_predicates.truncate(original_where_predicate_count);
+        attrs.truncate(original_attr_count);
     }
 
     predicates
@@ -0,0 +1,194 @@
+use proc_macro2::{Delimiter, Group, Ident, Punct, Spacing, TokenStream, TokenTree};
+use quote::{ToTokens, quote, quote_spanned};
+
+use crate::parse::parse_template;
+
+pub fn main(tokens: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    let tokens = TokenStream::from(tokens);
+
+    let mut result = TokenStream::new();
+    let mut errors = Vec::new();
+
+    match parse_template(tokens, &mut errors) {
+        Ok(template) => {
+            let attrs = &template.attrs;
+            let name = &template.name;
+
+            let macro_export = if template.is_exported {
+                quote! { #[macro_export] }
+            } else {
+                TokenStream::new()
+            };
+
+            // Each parameter becomes a `$T:ty` fragment, and each instance is
+            // either a single type or a bracketed list of types.
+            let fragments = template.params.iter().map(metavar).collect::<Vec<_>>();
+
+            let matcher = match fragments.as_slice() {
+                [fragment] => quote! { #fragment:ty },
+                fragments => quote! { [#(#fragments:ty),*] },
+            };
+
+            let mut body = template.body;
+            for (param, fragment) in template.params.iter().zip(&fragments) {
+                body = substitute_param(&body, param, fragment);
+            }
+
+            let dollar = Punct::new('$', Spacing::Alone);
+
+            result.extend(quote_spanned! {
+                name.span() =>
+                #(#attrs)*
+                #macro_export
+                macro_rules! #name {
+                    (#dollar(#matcher),* #dollar(,)?) => {
+                        #dollar(
+                            ::assert_impl_trait::assert_impl!(#body);
+                        )*
+                    };
+                }
+            });
+        }
+        Err(error) => errors.push(error),
+    }
+
+    for error in errors {
+        error.to_tokens(&mut result);
+    }
+
+    result.into()
+}
+
+/// Returns the `$T` that refers to the instance's type for `T`.
+fn metavar(param: &Ident) -> TokenStream {
+    let dollar = Punct::new('$', Spacing::Joint);
+
+    quote! { #dollar #param }
+}
+
+/// Replaces each use of the template parameter `param` with `replacement`,
+/// except in the groups that declare a parameter with the same name, like
+/// `for<T> { ... }`, `impl<T> ... {}` or `for T in [...] { ... }`, where `T`
+/// is the inner parameter.
+fn substitute_param(tokens: &TokenStream, param: &Ident, replacement: &TokenStream) -> TokenStream {
+    let tokens = tokens.clone().into_iter().collect::<Vec<_>>();
+    let mut result = TokenStream::new();
+    let mut i = 0;
+
+    while i < tokens.len() {
+        if declares(&tokens[i..], param)
+            && let Some(end) = group_end(&tokens[i..])
+        {
+            result.extend(tokens[i..=i + end].iter().cloned());
+            i += end + 1;
+            continue;
+        }
+
+        let is_after_lifetime_prefix = i > 0 && is_punct(tokens.get(i - 1), '\'');
+
+        match &tokens[i] {
+            TokenTree::Ident(ident) if ident == param && !is_after_lifetime_prefix => {
+                result.extend(replacement.clone());
+            }
+            TokenTree::Group(group) => {
+                let stream = substitute_param(&group.stream(), param, replacement);
+                let mut new_group = Group::new(group.delimiter(), stream);
+                new_group.set_span(group.span());
+
+                result.extend([TokenTree::Group(new_group)]);
+            }
+            token => result.extend([token.clone()]),
+        }
+
+        i += 1;
+    }
+
+    result
+}
+
+/// Returns whether the tokens start with a group that declares `param`, like
+/// `for<T>`, `impl<T: Clone>`, `for<..T; 0..=4>` or `for T in`.
+fn declares(tokens: &[TokenTree], param: &Ident) -> bool {
+    let name = param.to_string();
+    let is_param = |token: Option<&TokenTree>| is_ident(token, &name);
+
+    if is_ident(tokens.first(), "for") {
+        let rest = if is_ident(tokens.get(1), "const") {
+            &tokens[2..]
+        } else {
+            &tokens[1..]
+        };
+
+        if is_param(rest.first()) && is_ident(rest.get(1), "in") {
+            return true;
+        }
+    }
+
+    if !(is_ident(tokens.first(), "for") || is_ident(tokens.first(), "impl"))
+        || !is_punct(tokens.get(1), '<')
+    {
+        return false;
+    }
+
+    // The name of a parameter follows the `<` or a `,` of the list, after an
+    // optional `const` or `..`.
+    let mut depth = 0;
+    let mut is_param_start = false;
+
+    for (i, token) in tokens.iter().enumerate().skip(1) {
+        if is_punct(Some(token), '<') {
+            depth += 1;
+            is_param_start = depth == 1;
+            continue;
+        }
+
+        if is_punct(Some(token), '>') && !is_punct(tokens.get(i - 1), '-') {
+            depth -= 1;
+            if depth == 0 {
+                return false;
+            }
+        } else if depth == 1 && is_punct(Some(token), ',') {
+            is_param_start = true;
+            continue;
+        } else if is_param_start && (is_punct(Some(token), '.') || is_ident(Some(token), "const")) {
+            continue;
+        } else if is_param_start && is_param(Some(token)) {
+            return true;
+        }
+
+        is_param_start = false;
+    }
+
+    false
+}
+
+/// Returns the index of the braces that end the group at the start of the
+/// tokens, skipping braced const arguments in angle brackets.
+fn group_end(tokens: &[TokenTree]) -> Option<usize> {
+    let mut depth = 0;
+
+    for (i, token) in tokens.iter().enumerate() {
+        match token {
+            TokenTree::Group(group) if group.delimiter() == Delimiter::Brace && depth == 0 => {
+                return Some(i);
+            }
+            token if is_punct(Some(token), '<') => depth += 1,
+            token
+                if is_punct(Some(token), '>') && (i == 0 || !is_punct(tokens.get(i - 1), '-')) =>
+            {
+                depth -= 1;
+            }
+            _ => {}
+        }
+    }
+
+    None
+}
+
+fn is_ident(token: Option<&TokenTree>, expected: &str) -> bool {
+    matches!(token, Some(TokenTree::Ident(ident)) if ident == expected)
+}
+
+fn is_punct(token: Option<&TokenTree>, expected: char) -> bool {
+    matches!(token, Some(TokenTree::Punct(punct)) if punct.as_char() == expected)
+}
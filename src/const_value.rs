@@ -0,0 +1,177 @@
+use proc_macro2::{Delimiter, Group, Literal, Span, TokenStream, TokenTree};
+use quote::quote;
+
+/// The most values a const range is allowed to expand to.
+const MAX_RANGE_LEN: u128 = 1 << 16;
+
+/// A literal const generic argument that can be the start or end of a range
+/// in `for const N in 0..=32`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ConstValue {
+    /// An integer with its type suffix, like `8` or `255u8`.
+    Int(i128, String),
+    Bool(bool),
+    Char(char),
+}
+
+impl ConstValue {
+    /// Parses a `true`, `false`, char literal or integer literal, where the
+    /// integer literal may follow a `-`.
+    pub fn from_tokens(tokens: &[TokenTree]) -> Option<Self> {
+        match tokens {
+            [TokenTree::Ident(ident)] if ident == "true" => Some(Self::Bool(true)),
+            [TokenTree::Ident(ident)] if ident == "false" => Some(Self::Bool(false)),
+            [TokenTree::Literal(literal)] => parse_literal(&literal.to_string()),
+            [TokenTree::Punct(minus), TokenTree::Literal(literal)] if minus.as_char() == '-' => {
+                match parse_literal(&literal.to_string())? {
+                    Self::Int(value, suffix) => Some(Self::Int(-value, suffix)),
+                    Self::Bool(_) | Self::Char(_) => None,
+                }
+            }
+            _ => None,
+        }
+    }
+
+    /// Returns every value from `start` to `end`, including `end` if
+    /// `inclusive` is true.
+    pub fn range(start: &Self, end: &Self, inclusive: bool) -> Result<Vec<Self>, String> {
+        let (start_index, end_index) = match (start, end) {
+            (Self::Int(start, start_suffix), Self::Int(end, end_suffix)) => {
+                if !start_suffix.is_empty() && !end_suffix.is_empty() && start_suffix != end_suffix
+                {
+                    return Err(format!(
+                        "the range starts with a `{start_suffix}` and ends with a `{end_suffix}`"
+                    ));
+                }
+
+                (*start, *end)
+            }
+            (Self::Bool(start), Self::Bool(end)) => (*start as i128, *end as i128),
+            (Self::Char(start), Self::Char(end)) => (*start as i128, *end as i128),
+            _ => return Err("the start and end of the range have different types".to_string()),
+        };
+
+        if end_index < start_index {
+            return Err("the range ends before it starts".to_string());
+        }
+
+        let end_index = if inclusive {
+            end_index.checked_add(1).ok_or_else(|| {
+                format!("the range ends at {end_index}, which is too large to include")
+            })?
+        } else {
+            end_index
+        };
+
+        if end_index.saturating_sub(start_index) as u128 > MAX_RANGE_LEN {
+            return Err(format!("the range has more than {MAX_RANGE_LEN} values"));
+        }
+
+        Ok((start_index..end_index)
+            .filter_map(|index| match (start, end) {
+                (Self::Int(_, start_suffix), Self::Int(_, end_suffix)) => {
+                    let suffix = if start_suffix.is_empty() {
+                        end_suffix
+                    } else {
+                        start_suffix
+                    };
+
+                    Some(Self::Int(index, suffix.clone()))
+                }
+                (Self::Bool(_), _) => Some(Self::Bool(index != 0)),
+                // Skips the surrogate range.
+                (Self::Char(_), _) => char::from_u32(index as u32).map(Self::Char),
+                _ => unreachable!(),
+            })
+            .collect())
+    }
+
+    /// Returns the value as a single token that can be used as a const
+    /// generic argument, wrapping negative integers in braces.
+    pub fn to_token(&self, span: Span) -> TokenTree {
+        let mut result = match self {
+            Self::Int(value, suffix) if *value < 0 => {
+                let literal = int_literal(-value, suffix);
+                TokenTree::Group(Group::new(Delimiter::Brace, quote! { -#literal }))
+            }
+            Self::Int(value, suffix) => TokenTree::Literal(int_literal(*value, suffix)),
+            Self::Bool(value) => quote! { #value }
+                .into_iter()
+                .next()
+                .expect("a bool is a single token"),
+            Self::Char(value) => TokenTree::Literal(Literal::character(*value)),
+        };
+
+        result.set_span(span);
+
+        result
+    }
+}
+
+/// Returns a single token for a const generic argument written in a
+/// `for const N in [...]` list, wrapping it in braces unless it already is a
+/// single token.
+pub fn const_arg_token(tokens: TokenStream, span: Span) -> TokenTree {
+    let mut tokens = tokens.into_iter().collect::<Vec<_>>();
+    if tokens.len() == 1 {
+        return tokens.remove(0);
+    }
+
+    let mut result = Group::new(Delimiter::Brace, tokens.into_iter().collect());
+    result.set_span(span);
+
+    TokenTree::Group(result)
+}
+
+fn parse_literal(text: &str) -> Option<ConstValue> {
+    if let Some(inner) = text.strip_prefix('\'').and_then(|t| t.strip_suffix('\'')) {
+        return parse_char(inner).map(ConstValue::Char);
+    }
+
+    let digits_end = text.find(['i', 'u']).unwrap_or(text.len());
+    let (digits, suffix) = text.split_at(digits_end);
+    let digits = digits.replace('_', "");
+
+    let value = if let Some(hex) = digits.strip_prefix("0x") {
+        i128::from_str_radix(hex, 16)
+    } else if let Some(octal) = digits.strip_prefix("0o") {
+        i128::from_str_radix(octal, 8)
+    } else if let Some(binary) = digits.strip_prefix("0b") {
+        i128::from_str_radix(binary, 2)
+    } else {
+        digits.parse()
+    };
+
+    Some(ConstValue::Int(value.ok()?, suffix.to_string()))
+}
+
+fn parse_char(text: &str) -> Option<char> {
+    let mut chars = text.chars();
+
+    let result = match chars.next()? {
+        '\\' => match chars.next()? {
+            'n' => '\n',
+            'r' => '\r',
+            't' => '\t',
+            '0' => '\0',
+            '\\' => '\\',
+            '\'' => '\'',
+            '"' => '"',
+            'x' => return char::from_u32(u32::from_str_radix(chars.as_str(), 16).ok()?),
+            'u' => {
+                let hex = chars.as_str().strip_prefix('{')?.strip_suffix('}')?;
+                return char::from_u32(u32::from_str_radix(&hex.replace('_', ""), 16).ok()?);
+            }
+            _ => return None,
+        },
+        c => c,
+    };
+
+    chars.next().is_none().then_some(result)
+}
+
+fn int_literal(value: i128, suffix: &str) -> Literal {
+    format!("{value}{suffix}")
+        .parse()
+        .expect("an integer with a valid suffix is a valid literal")
+}
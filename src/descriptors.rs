@@ -0,0 +1,88 @@
+use proc_macro2::{Ident, TokenStream};
+use quote::{ToTokens, quote};
+
+use crate::{pretty::pretty, types::PredicateTree};
+
+/// Emits the module given by `#![descriptors(pub mod name)]`, which holds a
+/// `Descriptor` for each asserted predicate.
+///
+/// The module is named by the user so that invocations in the same module
+/// don't collide. Descriptors describe the predicates as written, so this
+/// runs before parameters are renamed and placeholders are replaced.
+pub fn push_descriptors(
+    trees: &[PredicateTree],
+    vis: &TokenStream,
+    module_name: &Ident,
+    tokens: &mut TokenStream,
+) {
+    let descriptors = PredicateTree::flatten(trees).into_iter().map(|context| {
+        let predicate = context.predicate;
+
+        let attrs = &context.attrs;
+        let text = pretty(&predicate.to_token_stream());
+        let generic_params = context
+            .generic_params
+            .iter()
+            .map(|p| pretty(&p.to_token_stream()));
+        let where_predicates = context
+            .where_predicates
+            .iter()
+            .map(|p| pretty(&p.to_token_stream()));
+
+        let location = predicate.span.unwrap();
+        let file = location.file();
+        let line = location.line() as u32;
+
+        let label = match &predicate.label {
+            Some(label) => {
+                let label = label.to_string();
+                quote! { ::core::option::Option::Some(#label) }
+            }
+            None => quote! { ::core::option::Option::None },
+        };
+
+        quote! {
+            #(#attrs)*
+            Descriptor {
+                predicate: #text,
+                generic_params: &[#(#generic_params),*],
+                where_predicates: &[#(#where_predicates),*],
+                file: #file,
+                line: #line,
+                label: #label,
+            }
+        }
+    });
+
+    tokens.extend(quote! {
+        /// The predicates asserted by an `assert_impl!` invocation.
+        #[allow(dead_code)]
+        #vis mod #module_name {
+            /// An asserted predicate and the context it is asserted in.
+            #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+            pub struct Descriptor {
+                /// The predicate, like `Vec<T>: Clone`.
+                pub predicate: &'static str,
+
+                /// The generic parameters of the `for<...>` groups around the
+                /// predicate, like `T: Clone`.
+                pub generic_params: &'static [&'static str],
+
+                /// The predicates of the `where` clauses around the predicate.
+                pub where_predicates: &'static [&'static str],
+
+                /// The file the predicate is written in.
+                pub file: &'static str,
+
+                /// The line the predicate starts on.
+                pub line: u32,
+
+                /// The label of the predicate, like `name` in `name: T: Clone`.
+                pub label: ::core::option::Option<&'static str>,
+            }
+
+            /// The asserted predicates, in the order they are written in.
+            pub const DESCRIPTORS: &[Descriptor] = &[#(#descriptors),*];
+        }
+    });
+}
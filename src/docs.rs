@@ -0,0 +1,60 @@
+use proc_macro2::{Delimiter, TokenStream, TokenTree};
+
+use crate::{pretty::pretty, types::PredicateTree};
+
+/// Returns a "Guaranteed implementations" documentation section that lists
+/// the asserted predicates, or an empty string if there are none.
+///
+/// Predicates are listed with their generic context, like
+/// `for<T> Vec<T>: Clone where T: Clone`, and predicates behind `#[cfg(...)]`
+/// are listed with their conditions, like rustdoc lists items behind crate
+/// features.
+pub fn guarantees_doc(trees: &[PredicateTree]) -> String {
+    let mut lines = String::new();
+
+    for predicate in PredicateTree::flatten(trees) {
+        lines.push_str(&format!("- `{}`", predicate.text()));
+
+        let cfgs = predicate
+            .attrs
+            .iter()
+            .filter_map(|attr| cfg_predicate(attr))
+            .map(|cfg| format!("`{}`", pretty(&cfg)))
+            .collect::<Vec<_>>();
+
+        if !cfgs.is_empty() {
+            lines.push_str(&format!(" (only with {})", cfgs.join(" and ")));
+        }
+
+        lines.push('\n');
+    }
+
+    if lines.is_empty() {
+        return lines;
+    }
+
+    format!(
+        "\n\n# Guaranteed implementations\n\n\
+        These implementations are asserted at compile time, so they are part of the API.\n\n\
+        {lines}"
+    )
+}
+
+/// Returns the `...` of a `#[cfg(...)]` attribute, or `None` for other
+/// attributes like `#[cfg_attr(...)]`.
+fn cfg_predicate(attr: &TokenStream) -> Option<TokenStream> {
+    let Some(TokenTree::Group(brackets)) = attr.clone().into_iter().nth(1) else {
+        return None;
+    };
+
+    let mut tokens = brackets.stream().into_iter();
+
+    match (tokens.next(), tokens.next()) {
+        (Some(TokenTree::Ident(name)), Some(TokenTree::Group(parens)))
+            if name == "cfg" && parens.delimiter() == Delimiter::Parenthesis =>
+        {
+            Some(parens.stream())
+        }
+        _ => None,
+    }
+}
@@ -0,0 +1,39 @@
+use proc_macro2::{Delimiter, TokenStream, TokenTree};
+use quote::quote;
+
+use crate::{docs::guarantees_doc, parse::parse_input, token_iter::TokenIter};
+
+pub fn main(
+    attr: proc_macro::TokenStream,
+    item: proc_macro::TokenStream,
+) -> proc_macro::TokenStream {
+    let attr = TokenStream::from(attr);
+    let item = &mut TokenIter::from(TokenStream::from(item));
+
+    // Errors are reported by the expansion of the assertions below.
+    let (_, trees, _) = parse_input(attr.clone(), &mut Vec::new());
+    let doc = guarantees_doc(&trees);
+
+    // The section goes after the item's own attributes so that it comes after
+    // its documentation.
+    let mut result = TokenStream::new();
+    while let Some(TokenTree::Punct(pound)) = item.peek()
+        && pound.as_char() == '#'
+        && matches!(item.peek2(), Some(TokenTree::Group(brackets)) if brackets.delimiter() == Delimiter::Bracket)
+    {
+        result.extend(item.next());
+        result.extend(item.next());
+    }
+
+    if !doc.is_empty() {
+        result.extend(quote! { #[doc = #doc] });
+    }
+
+    while let Some(token) = item.next() {
+        result.extend([token]);
+    }
+
+    result.extend(TokenStream::from(crate::assert_impl::main(attr.into())));
+
+    result.into()
+}
@@ -0,0 +1,66 @@
+use std::collections::HashSet;
+
+use proc_macro2::{Ident, Span, TokenStream, TokenTree};
+
+/// Hands out identifiers that are guaranteed not to collide with any
+/// identifier in the macro input.
+///
+/// `Span::mixed_site()` only makes local variables and labels hygienic, so
+/// items and generic parameters emitted by the macro need names that the user
+/// didn't write anywhere.
+#[derive(Debug, Clone, Default)]
+pub struct Names {
+    used: HashSet<String>,
+}
+
+impl Names {
+    pub fn new(tokens: &TokenStream) -> Self {
+        let mut result = Self::default();
+        result.reserve_all(tokens);
+
+        result
+    }
+
+    /// Returns `base`, or `base` followed by underscores if `base` is already
+    /// taken, and reserves the result.
+    pub fn fresh(&mut self, base: &str) -> String {
+        let mut result = base.to_string();
+        while self.used.contains(&result) {
+            result.push('_');
+        }
+
+        self.used.insert(result.clone());
+
+        result
+    }
+
+    /// Like `fresh`, but returns a mixed-site identifier.
+    pub fn fresh_ident(&mut self, base: &str) -> Ident {
+        Ident::new(&self.fresh(base), Span::mixed_site())
+    }
+
+    /// Like `fresh_ident`, but no identifier in the input starts with the
+    /// result either, so that the result followed by anything is fresh too.
+    pub fn fresh_prefix_ident(&mut self, base: &str) -> Ident {
+        let mut result = base.to_string();
+        while self.used.iter().any(|used| used.starts_with(&result)) {
+            result.push('_');
+        }
+
+        self.used.insert(result.clone());
+
+        Ident::new(&result, Span::mixed_site())
+    }
+
+    fn reserve_all(&mut self, tokens: &TokenStream) {
+        for token in tokens.clone() {
+            match token {
+                TokenTree::Ident(ident) => {
+                    self.used.insert(ident.to_string());
+                }
+                TokenTree::Group(group) => self.reserve_all(&group.stream()),
+                TokenTree::Punct(_) | TokenTree::Literal(_) => {}
+            }
+        }
+    }
+}
@@ -2,8 +2,19 @@
 #![doc = include_str!("../README.md")]
 
 mod assert_impl;
+mod assert_template;
+mod const_value;
+mod descriptors;
+mod docs;
+mod guarantees;
+mod hygiene;
+mod lint;
 mod parse;
+mod pretty;
+mod substitute;
+mod sugar;
 mod token_iter;
+mod type_sets;
 mod types;
 
 /// A simple macro for making compile-time assertions that a type implements a
@@ -47,10 +58,129 @@ mod types;
 ///     }
 /// );
 ///
+/// // Assert that `u8`, `u16` and `String` all implement `Clone` and `Debug`.
+/// assert_impl!(
+///     for T in [u8, u16, String] {
+///         T: Clone + core::fmt::Debug,
+///     }
+/// );
+///
 /// // Assert that `Debug` is a dyn-compatible trait.
 /// assert_impl!(dyn core::fmt::Debug:);
+///
+/// // Assert that for any `Copy` type `T`, `Option<T>` is `Copy`, and prove
+/// // that the condition can be met by instantiating the assertion with
+/// // `T = u8`.
+/// assert_impl!(
+///     for<T = u8> where T: Copy {
+///         Option<T>: Copy,
+///     }
+/// );
+/// ```
+///
+/// Lifetime bounds are checked like trait bounds, so these fail to compile
+/// because `T` and `'a` may not outlive `'a` and `'b`:
+///
+/// ```compile_fail
+/// use assert_impl_trait::assert_impl;
+///
+/// assert_impl!(for<'a, T> { T: 'a });
+/// ```
+///
+/// ```compile_fail
+/// use assert_impl_trait::assert_impl;
+///
+/// assert_impl!(for<'a, 'b> { &'a u8: Into<&'b u8> });
+/// ```
+///
+/// Collection presets assert the traits that depend on the item type, like
+/// `Clone`, directly if the item type is concrete, so this fails to compile
+/// because `Names` isn't `Clone`:
+///
+/// ```compile_fail
+/// use assert_impl_trait::assert_impl;
+///
+/// struct Names;
+///
+/// assert_impl!(collection!(
+///     Names,
+///     item = String,
+///     skip(into_iter, into_iter_ref, into_iter_mut, from_iter, extend, extend_ref, default, debug, partial_eq),
+/// ));
 /// ```
 #[proc_macro]
 pub fn assert_impl(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
     assert_impl::main(input)
 }
+
+/// Defines a reusable set of assertions as a declarative macro.
+///
+/// `assert_template!(name<T, ...> { ... })` generates a `name!` macro that
+/// calls [`assert_impl!`] with the body for each instance it is given, where
+/// `T` stands for the instance's type, except in groups like `for<T> { ... }`
+/// that declare their own `T`. Templates with a single parameter take
+/// a list of types, and templates with several parameters take a list of
+/// bracketed types like `name!([u8, u16], [u16, u32])`.
+///
+/// Starting the template with `pub` exports the macro from the crate with
+/// `#[macro_export]`. Crates that call an exported template have to depend
+/// on this crate too.
+///
+/// # Examples
+///
+/// ```
+/// use assert_impl_trait::assert_template;
+///
+/// assert_template!(
+///     /// Asserts that each type is a plain numeric type.
+///     numeric<T> {
+///         T: Copy + Default + core::ops::Add<Output = T>,
+///         [T; 4]: Default,
+///     }
+/// );
+///
+/// assert_template!(
+///     widening<Small, Large> {
+///         Large: From<Small>,
+///         Small: TryFrom<Large>,
+///     }
+/// );
+///
+/// numeric!(u8, i32, f64);
+/// widening!([u8, u16], [u16, u32]);
+/// ```
+#[proc_macro]
+pub fn assert_template(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    assert_template::main(input)
+}
+
+/// Asserts the predicates like [`assert_impl!`], and lists them in a
+/// "Guaranteed implementations" section of the item's documentation.
+///
+/// This tells users which implementations of the item are a promise, and
+/// which ones only exist because of how the item is implemented today.
+///
+/// # Examples
+///
+/// ```
+/// use assert_impl_trait::guarantees;
+///
+/// /// A list of values.
+/// #[guarantees(
+///     for<T: Clone> {
+///         List<T>: Clone,
+///     }
+///     for<T: Send> {
+///         List<T>: Send,
+///     }
+/// )]
+/// #[derive(Clone)]
+/// pub struct List<T>(Vec<T>);
+/// ```
+#[proc_macro_attribute]
+pub fn guarantees(
+    attr: proc_macro::TokenStream,
+    item: proc_macro::TokenStream,
+) -> proc_macro::TokenStream {
+    guarantees::main(attr, item)
+}
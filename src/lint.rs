@@ -0,0 +1,201 @@
+use proc_macro2::Span;
+use quote::ToTokens;
+
+use crate::types::{Config, GenericParam, Predicate, PredicateGroup, PredicateTree, Warning};
+
+/// A mistake that doesn't make the assertions wrong but is almost always
+/// unintended, like a copy-pasted predicate.
+///
+/// Lints are reported as warnings and can be silenced with
+/// `#![allow(lint_name)]` at the start of the macro input.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Lint {
+    /// A `for<...>` parameter that no asserted predicate uses.
+    UnusedGenericParam,
+
+    /// A group that contains no predicates.
+    EmptyGroup,
+
+    /// A predicate that is asserted twice in the same context.
+    DuplicatePredicate,
+
+    /// A `where` predicate that is already required by the same or an outer
+    /// group.
+    RedundantWherePredicate,
+}
+
+impl Lint {
+    pub const ALL: [Self; 4] = [
+        Self::UnusedGenericParam,
+        Self::EmptyGroup,
+        Self::DuplicatePredicate,
+        Self::RedundantWherePredicate,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::UnusedGenericParam => "unused_generic_param",
+            Self::EmptyGroup => "empty_group",
+            Self::DuplicatePredicate => "duplicate_predicate",
+            Self::RedundantWherePredicate => "redundant_where_predicate",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|lint| lint.name() == name)
+    }
+}
+
+/// Runs every lint that isn't allowed by `config` over the predicate trees.
+pub fn lint_trees(trees: &[PredicateTree], config: &Config) -> Vec<Warning> {
+    let mut linter = Linter {
+        config,
+        warnings: Vec::new(),
+    };
+
+    linter.lint_list(trees, &[]);
+
+    linter.warnings
+}
+
+struct Linter<'a> {
+    config: &'a Config,
+    warnings: Vec<Warning>,
+}
+
+impl Linter<'_> {
+    fn lint_list(&mut self, trees: &[PredicateTree], outer_where_predicates: &[&Predicate]) {
+        let mut asserted = Vec::new();
+
+        for tree in trees {
+            match tree {
+                PredicateTree::Group(group) => self.lint_group(group, outer_where_predicates),
+                PredicateTree::Predicate(predicate) => {
+                    let text = predicate.to_token_stream().to_string();
+
+                    if asserted.contains(&text) {
+                        self.warn(
+                            Lint::DuplicatePredicate,
+                            predicate.span,
+                            format!("`{text}` is already asserted in this context"),
+                        );
+                    }
+
+                    asserted.push(text);
+                }
+            }
+        }
+    }
+
+    fn lint_group(&mut self, group: &PredicateGroup, outer_where_predicates: &[&Predicate]) {
+        if group.predicates.is_empty() {
+            self.warn(
+                Lint::EmptyGroup,
+                group.span,
+                "this group doesn't assert any predicate",
+            );
+        }
+
+        for (i, param) in group.generic_params.iter().enumerate() {
+            // A parameter that only appears in the bound of another parameter
+            // or in a where-predicate can't be removed either.
+            let is_used = trees_use(&group.predicates, param)
+                || group
+                    .generic_params
+                    .iter()
+                    .enumerate()
+                    .any(|(j, other)| i != j && param.is_used_in(&other.tokens))
+                || group
+                    .where_predicates
+                    .iter()
+                    .any(|predicate| predicate_uses(predicate, param));
+
+            if !is_used {
+                self.warn(
+                    Lint::UnusedGenericParam,
+                    param.span,
+                    format!(
+                        "generic parameter `{}` isn't used by any predicate or bound",
+                        param.inline_arg,
+                    ),
+                );
+            }
+        }
+
+        // Outer predicates that mention a shadowed parameter are about a
+        // different parameter, even if they are spelled the same.
+        let mut where_predicates = outer_where_predicates
+            .iter()
+            .copied()
+            .filter(|predicate| {
+                !group
+                    .generic_params
+                    .iter()
+                    .any(|param| predicate_uses(predicate, param))
+            })
+            .collect::<Vec<_>>();
+
+        for predicate in &group.where_predicates {
+            let text = predicate.to_token_stream().to_string();
+
+            if where_predicates
+                .iter()
+                .any(|other| other.to_token_stream().to_string() == text)
+            {
+                self.warn(
+                    Lint::RedundantWherePredicate,
+                    predicate.span,
+                    format!("`{text}` is already required by this context"),
+                );
+            }
+
+            where_predicates.push(predicate);
+        }
+
+        self.lint_list(&group.predicates, &where_predicates);
+    }
+
+    fn warn(&mut self, lint: Lint, span: Span, message: impl Into<String>) {
+        if self.config.allowed_lints.contains(&lint) {
+            return;
+        }
+
+        self.warnings.push(Warning::new_at_span(
+            span,
+            format!(
+                "{}\n(silence this with `#![allow({})]`)",
+                message.into(),
+                lint.name(),
+            ),
+        ));
+    }
+}
+
+fn trees_use(trees: &[PredicateTree], param: &GenericParam) -> bool {
+    trees.iter().any(|tree| match tree {
+        PredicateTree::Predicate(predicate) => predicate_uses(predicate, param),
+        PredicateTree::Group(group) => {
+            if group
+                .generic_params
+                .iter()
+                .any(|inner| inner.shares_name_with(param))
+            {
+                return false;
+            }
+
+            group
+                .generic_params
+                .iter()
+                .any(|inner| param.is_used_in(&inner.tokens))
+                || group
+                    .where_predicates
+                    .iter()
+                    .any(|predicate| predicate_uses(predicate, param))
+                || trees_use(&group.predicates, param)
+        }
+    })
+}
+
+fn predicate_uses(predicate: &Predicate, param: &GenericParam) -> bool {
+    param.is_used_in(&predicate.left_side) || param.is_used_in(&predicate.bound)
+}
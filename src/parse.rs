@@ -1,92 +1,1287 @@
-use proc_macro2::{Delimiter, Group, Ident, Punct, Spacing, TokenStream, TokenTree};
+use proc_macro2::{Delimiter, Group, Ident, Literal, Punct, Spacing, Span, TokenStream, TokenTree};
 use quote::{TokenStreamExt, quote};
 
 use crate::{
+    const_value::{ConstValue, const_arg_token},
+    hygiene::Names,
+    lint::Lint,
+    pretty::words,
+    substitute::{Rewrite, Substitution, VariadicExpansion},
+    sugar::{
+        Wrapper, api_guidelines_group, collection_group, comparison_group, conversion_group,
+        forwarding_group, implication_group, operator_group,
+    },
     token_iter::TokenIter,
-    types::{Error, GenericParam, Predicate, PredicateGroup, PredicateTree},
+    type_sets::{TYPE_SET_NAMES, type_set},
+    types::{
+        Config, Error, GenericParam, ListElement, Predicate, PredicateGroup, PredicateTree,
+        Template, TreeSource, VariadicParam,
+    },
 };
 
-pub fn parse_predicate_list(tokens: TokenStream, errors: &mut Vec<Error>) -> Vec<PredicateTree> {
+/// Parses the whole macro input, which is a list of inner attributes followed
+/// by a predicate list.
+///
+/// Also returns the source of each tree in the list, which is its label if it
+/// has one.
+pub fn parse_input(
+    tokens: TokenStream,
+    errors: &mut Vec<Error>,
+) -> (Config, Vec<PredicateTree>, Vec<TreeSource>) {
+    let tokens = &mut TokenIter::from(tokens);
+
+    let config = consume_config(tokens, errors);
+
+    let mut sources = Vec::new();
+    let trees = consume_predicate_list(tokens, errors, &mut sources);
+
+    (config, trees, sources)
+}
+
+/// Parses an `assert_template!` input like `pub numeric<T> { T: Copy }`.
+///
+/// The body is also parsed as `assert_impl!` input so that syntax errors are
+/// reported where the template is defined.
+pub fn parse_template(tokens: TokenStream, errors: &mut Vec<Error>) -> Result<Template, Error> {
     let tokens = &mut TokenIter::from(tokens);
+
+    let mut attrs = Vec::new();
+    while let Some(pound) = consume_optional_punct(tokens, '#') {
+        let brackets = consume_delimiter(tokens, Delimiter::Bracket)?;
+        attrs.push(quote! { #pound #brackets });
+    }
+
+    let is_exported = consume_optional_ident(tokens, "pub").is_some();
+    let name = consume_any_ident(tokens)?;
+
+    consume_punct(tokens, '<')?;
+    let mut params = Vec::new();
+    while tokens.peek().is_some() && !peek_punct(tokens, '>') {
+        if peek_punct(tokens, '\'') || peek_ident(tokens, "const") {
+            return Err(Error::new_at_span(
+                tokens.span(),
+                "template parameters must be type parameters",
+            ));
+        }
+
+        params.push(consume_any_ident(tokens)?);
+
+        if consume_optional_punct(tokens, ',').is_none() {
+            break;
+        }
+    }
+    consume_punct(tokens, '>')?;
+
+    let braces = consume_delimiter(tokens, Delimiter::Brace)?;
+    if let Some(token) = tokens.peek() {
+        return Err(Error::new_at_span(token.span(), "unexpected token"));
+    }
+
+    parse_input(braces.stream(), errors);
+
+    Ok(Template {
+        attrs,
+        is_exported,
+        name,
+        params,
+        body: braces.stream(),
+    })
+}
+
+pub fn parse_predicate_list(tokens: TokenStream, errors: &mut Vec<Error>) -> Vec<PredicateTree> {
+    consume_predicate_list(&mut TokenIter::from(tokens), errors, &mut Vec::new())
+}
+
+////////////////////////////////////////////////////////////////////////////////
+// Consume
+////////////////////////////////////////////////////////////////////////////////
+
+fn consume_config(tokens: &mut TokenIter, errors: &mut Vec<Error>) -> Config {
+    let mut config = Config::default();
+
+    while peek_punct(tokens, '#') && peek2_punct(tokens, '!') {
+        tokens.next();
+        tokens.next();
+
+        let brackets = match consume_delimiter(tokens, Delimiter::Bracket) {
+            Ok(brackets) => brackets,
+            Err(error) => {
+                errors.push(error);
+                continue;
+            }
+        };
+
+        if let Err(error) = consume_config_attribute(&mut brackets.stream().into(), &mut config) {
+            errors.push(error);
+        }
+    }
+
+    config
+}
+
+fn consume_config_attribute(tokens: &mut TokenIter, config: &mut Config) -> Result<(), Error> {
+    let name = consume_any_ident(tokens)?;
+
+    if name == "test" {
+        config.emit_tests = true;
+
+        if let Some(parens) = consume_optional_delimiter(tokens, Delimiter::Parenthesis) {
+            let parens = &mut TokenIter::from(parens.stream());
+
+            consume_ident(parens, "mod")?;
+            config.test_module = Some(consume_test_module_name(parens)?);
+        }
+    } else if name == "explain" {
+        config.explain = true;
+
+        if let Some(parens) = consume_optional_delimiter(tokens, Delimiter::Parenthesis) {
+            let parens = &mut TokenIter::from(parens.stream());
+
+            consume_ident(parens, "tokens")?;
+            config.explain_tokens = true;
+
+            if let Some(token) = parens.peek() {
+                return Err(Error::new_at_span(token.span(), "unexpected token"));
+            }
+        }
+    } else if name == "docs" {
+        let parens = consume_delimiter(tokens, Delimiter::Parenthesis)?;
+        let parens = &mut TokenIter::from(parens.stream());
+
+        config.docs_macro = Some(consume_any_ident(parens)?);
+
+        if let Some(token) = parens.peek() {
+            return Err(Error::new_at_span(token.span(), "unexpected token"));
+        }
+    } else if name == "descriptors" {
+        let parens = consume_delimiter(tokens, Delimiter::Parenthesis)?;
+        let parens = &mut TokenIter::from(parens.stream());
+
+        let mut vis = TokenStream::new();
+        while parens.peek().is_some() && !peek_ident(parens, "mod") {
+            vis.append(parens.next().expect("peek ensures a token exists"));
+        }
+
+        consume_ident(parens, "mod")?;
+        let module_name = consume_any_ident(parens)?;
+
+        if let Some(token) = parens.peek() {
+            return Err(Error::new_at_span(token.span(), "unexpected token"));
+        }
+
+        config.descriptors = Some((vis, module_name));
+    } else if name == "allow" {
+        let parens = consume_delimiter(tokens, Delimiter::Parenthesis)?;
+        let parens = &mut TokenIter::from(parens.stream());
+
+        while parens.peek().is_some() {
+            let lint_name = consume_any_ident(parens)?;
+            let lint = Lint::from_name(&lint_name.to_string()).ok_or_else(|| {
+                Error::new_at_span(lint_name.span(), format!("unknown lint `{lint_name}`"))
+            })?;
+
+            config.allowed_lints.push(lint);
+
+            if consume_optional_punct(parens, ',').is_none() {
+                break;
+            }
+        }
+
+        if let Some(token) = parens.peek() {
+            return Err(Error::new_at_span(token.span(), "expected `,`"));
+        }
+    } else {
+        return Err(Error::new_at_span(
+            name.span(),
+            format!("unknown attribute `{name}`"),
+        ));
+    }
+
+    if let Some(token) = tokens.peek() {
+        return Err(Error::new_at_span(token.span(), "unexpected token"));
+    }
+
+    Ok(())
+}
+
+/// Consumes the `name` or `name<T>` of `#![test(mod name<T>)]`, which is
+/// followed by the words of the generic arguments, like `name_u8`, so that
+/// each instance of a template gets its own module.
+fn consume_test_module_name(tokens: &mut TokenIter) -> Result<Ident, Error> {
+    let name = consume_any_ident(tokens)?;
+    let args = if peek_punct(tokens, '<') {
+        consume_type_expr(tokens)?
+    } else {
+        TokenStream::new()
+    };
+
+    if let Some(token) = tokens.peek() {
+        return Err(Error::new_at_span(token.span(), "unexpected token"));
+    }
+
+    let mut module_name = name.to_string();
+    for word in words(&args) {
+        module_name.push('_');
+        module_name.push_str(&word);
+    }
+
+    Ok(Ident::new(&module_name, name.span()))
+}
+
+fn consume_predicate_list(
+    tokens: &mut TokenIter,
+    errors: &mut Vec<Error>,
+    sources: &mut Vec<TreeSource>,
+) -> Vec<PredicateTree> {
     let mut result = Vec::new();
 
-    while tokens.peek().is_some() {
-        let predicate = match consume_predicate_tree(tokens, errors) {
-            Ok(predicate) => predicate,
-            Err(error) => {
-                errors.push(error);
-                break;
+    while tokens.peek().is_some() {
+        let predicate = match consume_attributed_predicate_tree(tokens, errors) {
+            Ok((predicate, source)) => {
+                sources.push(source);
+                predicate
+            }
+            Err(error) => {
+                errors.push(error);
+                break;
+            }
+        };
+
+        if consume_optional_punct(tokens, ',').is_none()
+            && let Some(token) = tokens.peek()
+            && !matches!(predicate, PredicateTree::Group(_))
+        {
+            errors.push(Error::new_at_span(token.span(), "expected `,`"));
+        }
+
+        result.push(predicate);
+    }
+
+    result
+}
+
+/// Consumes a predicate tree with outer attributes like `#[cfg(...)]` and an
+/// optional label like `name:`.
+///
+/// `cfg` attributes are applied to the items emitted for the tree, and the
+/// label and `#[reason = "..."]` are given to every predicate of the tree that
+/// doesn't have its own. Also returns the label, or the tokens of the tree if
+/// there is no label.
+fn consume_attributed_predicate_tree(
+    tokens: &mut TokenIter,
+    errors: &mut Vec<Error>,
+) -> Result<(PredicateTree, TreeSource), Error> {
+    let span = tokens.span();
+
+    let mut attrs = Vec::new();
+    let mut reason = None;
+    while peek_punct(tokens, '#') {
+        match consume_outer_attribute(tokens)? {
+            OuterAttribute::Cfg(attr) => attrs.push(attr),
+            OuterAttribute::Reason(literal) => reason = Some(literal),
+        }
+    }
+
+    let label = consume_optional_label(tokens);
+    let start = tokens.clone();
+
+    let mut tree = consume_predicate_tree(tokens, errors)?;
+    let source = match &label {
+        Some(label) => TreeSource::Label(label.clone()),
+        None => TreeSource::Tokens(tokens.consumed_since(&start)),
+    };
+
+    if let Some(label) = label {
+        // The label names the check function, so it must not shadow an item
+        // that the labelled predicates use.
+        let mut names = Names::new(&tokens.consumed_since(&start));
+        let label = Ident::new(&names.fresh(&label.to_string()), label.span());
+
+        set_label(&mut tree, &label);
+    }
+
+    if let Some(reason) = reason {
+        set_reason(&mut tree, &reason);
+    }
+
+    if attrs.is_empty() {
+        return Ok((tree, source));
+    }
+
+    let mut group = match tree {
+        PredicateTree::Group(group) => group,
+        PredicateTree::Predicate(predicate) => {
+            PredicateGroup::new(vec![PredicateTree::Predicate(predicate)], span)
+        }
+    };
+
+    attrs.append(&mut group.attrs);
+    group.attrs = attrs;
+
+    Ok((PredicateTree::Group(group), source))
+}
+
+/// An attribute in front of a predicate tree.
+enum OuterAttribute {
+    Cfg(TokenStream),
+    Reason(Literal),
+}
+
+/// Consumes a `#[cfg(...)]`, `#[cfg_attr(...)]` or `#[reason = "..."]`
+/// attribute.
+fn consume_outer_attribute(tokens: &mut TokenIter) -> Result<OuterAttribute, Error> {
+    let pound = consume_punct(tokens, '#')?;
+    let brackets = consume_delimiter(tokens, Delimiter::Bracket)?;
+
+    let attr_tokens = &mut TokenIter::from(brackets.stream());
+
+    if consume_optional_ident(attr_tokens, "reason").is_some() {
+        consume_punct(attr_tokens, '=')?;
+
+        let literal = match attr_tokens.next() {
+            Some(TokenTree::Literal(literal)) if literal.to_string().starts_with(['"', 'r']) => {
+                literal
+            }
+            _ => {
+                return Err(Error::new_at_span(
+                    brackets.span(),
+                    "expected a string like `#[reason = \"...\"]`",
+                ));
+            }
+        };
+
+        if let Some(token) = attr_tokens.next() {
+            return Err(Error::new_at_span(token.span(), "unexpected token"));
+        }
+
+        return Ok(OuterAttribute::Reason(literal));
+    }
+
+    if !peek_ident(attr_tokens, "cfg") && !peek_ident(attr_tokens, "cfg_attr") {
+        return Err(Error::new_at_span(
+            brackets.span(),
+            "only `#[cfg(...)]`, `#[cfg_attr(...)]` and `#[reason = \"...\"]` can be applied to predicates",
+        ));
+    }
+
+    Ok(OuterAttribute::Cfg(quote! { #pound #brackets }))
+}
+
+/// Consumes a label like `name:` if the tokens after it form a predicate tree.
+///
+/// A label looks like the start of a predicate like `T: Clone`, so the rest
+/// is parsed first to tell them apart.
+fn consume_optional_label(tokens: &mut TokenIter) -> Option<Ident> {
+    let Some(TokenTree::Ident(label)) = tokens.peek() else {
+        return None;
+    };
+
+    let is_single_colon = matches!(
+        tokens.peek2(),
+        Some(TokenTree::Punct(colon)) if colon.as_char() == ':' && colon.spacing() == Spacing::Alone
+    );
+
+    if !is_single_colon {
+        return None;
+    }
+
+    let label = label.clone();
+
+    let mut rest = tokens.clone();
+    rest.next();
+    rest.next();
+
+    if consume_predicate_tree(&mut rest.clone(), &mut Vec::new()).is_err() {
+        return None;
+    }
+
+    *tokens = rest;
+
+    Some(label)
+}
+
+/// Labels the predicates of a tree that don't have a label yet.
+fn set_label(tree: &mut PredicateTree, label: &Ident) {
+    match tree {
+        PredicateTree::Group(group) => {
+            for tree in &mut group.predicates {
+                set_label(tree, label);
+            }
+        }
+        PredicateTree::Predicate(predicate) => {
+            predicate.label.get_or_insert_with(|| label.clone());
+        }
+    }
+}
+
+/// Gives a reason to the predicates of a tree that don't have one yet.
+fn set_reason(tree: &mut PredicateTree, reason: &Literal) {
+    match tree {
+        PredicateTree::Group(group) => {
+            for tree in &mut group.predicates {
+                set_reason(tree, reason);
+            }
+        }
+        PredicateTree::Predicate(predicate) => {
+            predicate.reason.get_or_insert_with(|| reason.clone());
+        }
+    }
+}
+
+fn consume_predicate_tree(
+    tokens: &mut TokenIter,
+    errors: &mut Vec<Error>,
+) -> Result<PredicateTree, Error> {
+    let span = tokens.span();
+
+    if consume_optional_ident(tokens, "for").is_some() {
+        if !peek_punct(tokens, '<') {
+            return consume_iteration_group(tokens, span, errors);
+        }
+
+        let (generic_params, variadic_param) = consume_generic_params(tokens, errors)?;
+
+        let where_predicates = consume_optional_where_clause(tokens)?.unwrap_or_default();
+
+        let (predicates, group_tokens) = if peek_ident(tokens, "trait") {
+            let start = tokens.clone();
+            let relation = consume_trait_relation(tokens)?;
+
+            (vec![relation], tokens.consumed_since(&start))
+        } else {
+            let braces = consume_delimiter(tokens, Delimiter::Brace)?;
+
+            (
+                parse_predicate_list(braces.stream(), errors),
+                braces.stream(),
+            )
+        };
+
+        let group = PredicateGroup {
+            generic_params,
+            where_predicates,
+            ..PredicateGroup::new(predicates, span)
+        };
+
+        return Ok(finish_generic_group(group, variadic_param, &group_tokens));
+    }
+
+    if let Some(where_predicates) = consume_optional_where_clause(tokens)? {
+        let braces = consume_delimiter(tokens, Delimiter::Brace)?;
+        let predicates = parse_predicate_list(braces.stream(), errors);
+
+        return Ok(PredicateTree::Group(PredicateGroup {
+            where_predicates,
+            ..PredicateGroup::new(predicates, span)
+        }));
+    }
+
+    if peek_ident(tokens, "impl")
+        || peek_ident(tokens, "unsafe")
+            && matches!(tokens.peek2(), Some(TokenTree::Ident(ident)) if ident == "impl")
+    {
+        return consume_impl_header(tokens, errors);
+    }
+
+    if peek_ident(tokens, "trait") {
+        return consume_trait_relation(tokens);
+    }
+
+    if let Some(braces) = consume_optional_delimiter(tokens, Delimiter::Brace) {
+        return consume_type_set_predicate(tokens, &braces);
+    }
+
+    if peek_ident(tokens, "ops") && peek2_delimiter(tokens, Delimiter::Parenthesis) {
+        return consume_operator_predicate(tokens);
+    }
+
+    if peek_ident(tokens, "fwd") && peek2_delimiter(tokens, Delimiter::Parenthesis) {
+        return consume_forwarding_predicate(tokens);
+    }
+
+    if peek_ident(tokens, "collection") && peek2_punct(tokens, '!') {
+        return consume_collection_predicate(tokens);
+    }
+
+    if peek_ident(tokens, "api_guidelines") && peek2_punct(tokens, '!') {
+        return consume_api_guidelines_predicate(tokens);
+    }
+
+    if peek_ident(tokens, "compare") && peek2_punct(tokens, '!') {
+        return consume_comparison_predicate(tokens);
+    }
+
+    if peek_ident(tokens, "convert") && peek2_punct(tokens, '!') {
+        return consume_conversion_predicate(tokens);
+    }
+
+    Ok(PredicateTree::Predicate(consume_predicate(tokens)?))
+}
+
+/// Consumes the `<...>` parameter list of a `for<...>` group or `impl<...>`
+/// header, which may contain one variadic parameter.
+fn consume_generic_params(
+    tokens: &mut TokenIter,
+    errors: &mut Vec<Error>,
+) -> Result<(Vec<GenericParam>, Option<VariadicParam>), Error> {
+    consume_punct(tokens, '<')?;
+
+    let mut generic_params = Vec::new();
+    let mut variadic_param = None;
+    while tokens.peek().is_some() && !peek_punct(tokens, '>') {
+        if peek_punct(tokens, '.') && peek2_punct(tokens, '.') {
+            tokens.next();
+            tokens.next();
+
+            let param = consume_variadic_param(tokens)?;
+            if variadic_param.is_some() {
+                errors.push(Error::new_at_span(
+                    param.span,
+                    "a group can only declare one variadic parameter",
+                ));
+            }
+
+            variadic_param = Some(param);
+        } else {
+            generic_params.push(consume_generic_param(tokens)?);
+        }
+
+        if consume_optional_punct(tokens, ',').is_none() {
+            break;
+        }
+    }
+
+    consume_punct(tokens, '>')?;
+
+    if let Some(i) = (1..generic_params.len())
+        .find(|&i| generic_params[i].is_lifetime && !generic_params[i - 1].is_lifetime)
+    {
+        errors.push(Error::new_at_span(
+            generic_params[i].span,
+            "lifetime parameters must be declared prior to type and const parameters",
+        ));
+    }
+
+    Ok((generic_params, variadic_param))
+}
+
+/// Returns a group with generic parameters as a tree, expanding it for each
+/// arity of its variadic parameter if it has one.
+///
+/// `group_tokens` are the tokens of the group's predicates, whose names the
+/// expanded parameters must not collide with.
+fn finish_generic_group(
+    group: PredicateGroup,
+    variadic_param: Option<VariadicParam>,
+    group_tokens: &TokenStream,
+) -> PredicateTree {
+    let Some(variadic_param) = variadic_param else {
+        return PredicateTree::Group(group);
+    };
+
+    let generic_params = &group.generic_params;
+    let where_predicates = &group.where_predicates;
+
+    let mut names = Names::new(&quote! { #(#generic_params)* #(#where_predicates)* #group_tokens });
+    expand_variadic_group(group, variadic_param, &mut names)
+}
+
+/// Consumes an impl header like `impl<T: Clone> Clone for Vec<T> where ...`
+/// or `impl dyn Trait` and desugars it into a group that asserts the impl.
+///
+/// Because a where clause would continue into the next predicate, headers
+/// with one have to be followed by `;`, `{}` or the end of the list.
+fn consume_impl_header(
+    tokens: &mut TokenIter,
+    errors: &mut Vec<Error>,
+) -> Result<PredicateTree, Error> {
+    let span = tokens.span();
+    let start = tokens.clone();
+
+    consume_optional_ident(tokens, "unsafe");
+    consume_ident(tokens, "impl")?;
+
+    let (generic_params, variadic_param) = if peek_punct(tokens, '<') {
+        consume_generic_params(tokens, errors)?
+    } else {
+        (Vec::new(), None)
+    };
+
+    let predicate_span = tokens.span();
+    let predicate = if peek_ident(tokens, "dyn") {
+        let left_side = consume_impl_type(tokens, None)?;
+
+        Predicate {
+            unsized_left_side: true,
+            ..Predicate::new(left_side, TokenStream::new(), predicate_span)
+        }
+    } else {
+        if peek_punct(tokens, '!') {
+            return Err(Error::new_at_span(
+                predicate_span,
+                "negative impls can't be asserted",
+            ));
+        }
+
+        let bound = consume_impl_type(tokens, Some("for"))?;
+        consume_ident(tokens, "for")?;
+        let left_side = consume_impl_type(tokens, None)?;
+
+        Predicate {
+            unsized_left_side: true,
+            ..Predicate::new(left_side, bound, predicate_span)
+        }
+    };
+
+    let where_predicates = consume_optional_where_clause(tokens)?.unwrap_or_default();
+
+    if let Some(braces) = consume_optional_delimiter(tokens, Delimiter::Brace) {
+        if !braces.stream().is_empty() {
+            return Err(Error::new_at_span(
+                braces.span(),
+                "impl headers can't have items, expected `{}`",
+            ));
+        }
+    } else {
+        consume_optional_punct(tokens, ';');
+    }
+
+    let group = PredicateGroup {
+        generic_params,
+        where_predicates,
+        ..PredicateGroup::new(vec![PredicateTree::Predicate(predicate)], span)
+    };
+
+    Ok(finish_generic_group(
+        group,
+        variadic_param,
+        &tokens.consumed_since(&start),
+    ))
+}
+
+/// Consumes the trait or self type of an impl header, which ends at `where`,
+/// `{`, `,`, `;` or `stop_keyword` outside of angle brackets.
+fn consume_impl_type(
+    tokens: &mut TokenIter,
+    stop_keyword: Option<&str>,
+) -> Result<TokenStream, Error> {
+    let mut result = TokenStream::new();
+    let mut depth = 0;
+
+    while let Some(token) = tokens.peek() {
+        let is_end = match token {
+            TokenTree::Ident(ident) => {
+                depth == 0 && (ident == "where" || stop_keyword.is_some_and(|k| ident == k))
+            }
+            TokenTree::Punct(punct) => depth == 0 && matches!(punct.as_char(), ',' | ';'),
+            TokenTree::Group(group) => depth == 0 && group.delimiter() == Delimiter::Brace,
+            TokenTree::Literal(_) => false,
+        };
+
+        if is_end {
+            break;
+        }
+
+        if peek_punct(tokens, '-') && peek2_punct(tokens, '>') {
+            result.append(tokens.next().expect("peek ensures a token exists"));
+        } else if peek_punct(tokens, '<') {
+            depth += 1;
+        } else if peek_punct(tokens, '>') {
+            depth -= 1;
+        }
+
+        result.append(tokens.next().expect("peek ensures a token exists"));
+    }
+
+    if result.is_empty() {
+        return Err(Error::new_at_span(
+            tokens.span(),
+            "expected a type expression",
+        ));
+    }
+
+    Ok(result)
+}
+
+/// Consumes a `trait A => B` or `trait A <=> B` relation and desugars it into
+/// groups that assert that every implementor of one side implements the
+/// other side.
+fn consume_trait_relation(tokens: &mut TokenIter) -> Result<PredicateTree, Error> {
+    let span = consume_ident(tokens, "trait")?.span();
+
+    let mut left = TokenStream::new();
+    let mut depth = 0;
+    let is_equivalence = loop {
+        if depth == 0 && peek_punct(tokens, '=') && peek2_punct(tokens, '>') {
+            break false;
+        }
+
+        if depth == 0 && peek_punct(tokens, '<') && peek2_punct(tokens, '=') {
+            tokens.next();
+            break true;
+        }
+
+        if tokens.peek().is_none() {
+            return Err(Error::new_at_span(tokens.span(), "expected `=>` or `<=>`"));
+        }
+
+        if peek_punct(tokens, '-') && peek2_punct(tokens, '>') {
+            left.append(tokens.next().expect("peek ensures a token exists"));
+        } else if peek_punct(tokens, '<') {
+            depth += 1;
+        } else if peek_punct(tokens, '>') {
+            depth -= 1;
+        }
+
+        left.append(tokens.next().expect("peek ensures a token exists"));
+    };
+
+    let arrow_span = tokens.span();
+    consume_punct(tokens, '=')?;
+    consume_punct(tokens, '>')?;
+
+    if left.is_empty() {
+        return Err(Error::new_at_span(
+            arrow_span,
+            "expected a trait before the arrow",
+        ));
+    }
+
+    let right = consume_type_expr(tokens)?;
+
+    let mut predicates = vec![PredicateTree::Group(implication_group(
+        left.clone(),
+        right.clone(),
+        arrow_span,
+    ))];
+
+    if is_equivalence {
+        predicates.push(PredicateTree::Group(implication_group(
+            right, left, arrow_span,
+        )));
+    }
+
+    Ok(PredicateTree::Group(PredicateGroup::new(predicates, span)))
+}
+
+/// Consumes the rest of a predicate like `{unsigned}: Bits` after its type
+/// sets, and desugars it into a predicate for each type.
+fn consume_type_set_predicate(
+    tokens: &mut TokenIter,
+    braces: &Group,
+) -> Result<PredicateTree, Error> {
+    let elements = consume_type_sets(braces)?;
+
+    consume_punct(tokens, ':')?;
+    let bound = consume_optional_type_expr(tokens).unwrap_or_default();
+
+    let predicates = elements
+        .into_iter()
+        .map(|element| {
+            let predicate = Predicate::new(element.value.into(), bound.clone(), braces.span());
+
+            PredicateTree::Group(PredicateGroup {
+                attrs: element.attrs,
+                ..PredicateGroup::new(vec![PredicateTree::Predicate(predicate)], braces.span())
+            })
+        })
+        .collect();
+
+    Ok(PredicateTree::Group(PredicateGroup::new(
+        predicates,
+        braces.span(),
+    )))
+}
+
+/// Consumes a predicate like `ops(A, B) -> Out: Add + Sub` and desugars it
+/// into a group that asserts each operator for owned and borrowed operands.
+fn consume_operator_predicate(tokens: &mut TokenIter) -> Result<PredicateTree, Error> {
+    let span = consume_ident(tokens, "ops")?.span();
+    let parens = consume_delimiter(tokens, Delimiter::Parenthesis)?;
+
+    let operand_tokens = &mut TokenIter::from(parens.stream());
+    let operands = consume_operands(operand_tokens)?;
+
+    if let Some(token) = operand_tokens.peek() {
+        return Err(Error::new_at_span(token.span(), "expected `,`"));
+    }
+
+    if !(1..=2).contains(&operands.len()) {
+        return Err(Error::new_at_span(
+            parens.span(),
+            "expected one or two operands",
+        ));
+    }
+
+    let output = if peek_punct(tokens, '-') && peek2_punct(tokens, '>') {
+        tokens.next();
+        tokens.next();
+
+        Some(consume_type_expr(tokens)?)
+    } else {
+        None
+    };
+
+    consume_punct(tokens, ':')?;
+    let bound = consume_type_expr(tokens)?;
+
+    let traits = split_punctuated(bound, '+')
+        .into_iter()
+        .map(|name| {
+            let name = name.into_iter().collect::<Vec<_>>();
+            match name.as_slice() {
+                [TokenTree::Ident(ident)] => Ok(ident.clone()),
+                _ => Err(Error::new_at_span(
+                    name.first().map_or(span, TokenTree::span),
+                    "expected the name of an operator trait",
+                )),
+            }
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    Ok(PredicateTree::Group(operator_group(
+        operands, output, traits, span,
+    )?))
+}
+
+/// Consumes a `compare!(A, B, C; ord; except(A == B))` predicate and
+/// desugars it into a group that asserts the comparisons between every
+/// ordered pair of the types.
+fn consume_comparison_predicate(tokens: &mut TokenIter) -> Result<PredicateTree, Error> {
+    let span = consume_ident(tokens, "compare")?.span();
+    consume_punct(tokens, '!')?;
+    let parens = consume_delimiter(tokens, Delimiter::Parenthesis)?;
+
+    let tokens = &mut TokenIter::from(parens.stream());
+    let types = consume_operands(tokens)?;
+
+    let mut is_ord = false;
+    let mut exceptions = Vec::new();
+    while consume_optional_punct(tokens, ';').is_some() {
+        if consume_optional_ident(tokens, "ord").is_some() {
+            is_ord = true;
+        } else if consume_optional_ident(tokens, "except").is_some() {
+            let parens = consume_delimiter(tokens, Delimiter::Parenthesis)?;
+            let tokens = &mut TokenIter::from(parens.stream());
+
+            while tokens.peek().is_some() {
+                let span = tokens.span();
+                let left = Group::new(Delimiter::None, consume_type_expr(tokens)?);
+                consume_punct(tokens, '=')?;
+                consume_punct(tokens, '=')?;
+                let right = Group::new(Delimiter::None, consume_type_expr(tokens)?);
+
+                exceptions.push((
+                    TokenTree::Group(left).into(),
+                    TokenTree::Group(right).into(),
+                    span,
+                ));
+
+                if consume_optional_punct(tokens, ',').is_none() {
+                    break;
+                }
+            }
+
+            if let Some(token) = tokens.peek() {
+                return Err(Error::new_at_span(token.span(), "expected `,`"));
             }
-        };
+        } else {
+            return Err(Error::new_at_span(
+                tokens.span(),
+                "expected `ord` or `except(...)`",
+            ));
+        }
+    }
 
-        if consume_optional_punct(tokens, ',').is_none()
-            && let Some(token) = tokens.peek()
-            && !matches!(predicate, PredicateTree::Group(_))
-        {
-            errors.push(Error::new_at_span(token.span(), "expected `,`"));
+    if let Some(token) = tokens.peek() {
+        return Err(Error::new_at_span(token.span(), "expected `,` or `;`"));
+    }
+
+    Ok(PredicateTree::Group(comparison_group(
+        types, is_ord, exceptions, span,
+    )?))
+}
+
+/// Consumes a `convert!(A -> B -> C, A -> D; transitive)` predicate and
+/// desugars it into a group that asserts the conversions along each chain.
+fn consume_conversion_predicate(tokens: &mut TokenIter) -> Result<PredicateTree, Error> {
+    let span = consume_ident(tokens, "convert")?.span();
+    consume_punct(tokens, '!')?;
+    let parens = consume_delimiter(tokens, Delimiter::Parenthesis)?;
+
+    let tokens = &mut TokenIter::from(parens.stream());
+    let mut chains = Vec::new();
+    while tokens.peek().is_some() && !peek_punct(tokens, ';') {
+        let chain_span = tokens.span();
+        let chain = consume_conversion_chain(tokens)?;
+        if chain.len() < 2 {
+            return Err(Error::new_at_span(
+                chain_span,
+                "expected a chain of at least two types like `A -> B`",
+            ));
         }
 
-        result.push(predicate);
+        chains.push(chain);
+
+        if consume_optional_punct(tokens, ',').is_none() {
+            break;
+        }
     }
 
-    result
+    let mut is_transitive = false;
+    let mut is_forward_only = false;
+    while consume_optional_punct(tokens, ';').is_some() {
+        if consume_optional_ident(tokens, "transitive").is_some() {
+            is_transitive = true;
+        } else if consume_optional_ident(tokens, "forward_only").is_some() {
+            is_forward_only = true;
+        } else {
+            return Err(Error::new_at_span(
+                tokens.span(),
+                "expected `transitive` or `forward_only`",
+            ));
+        }
+    }
+
+    if let Some(token) = tokens.peek() {
+        return Err(Error::new_at_span(token.span(), "expected `,` or `;`"));
+    }
+
+    Ok(PredicateTree::Group(conversion_group(
+        chains,
+        is_transitive,
+        is_forward_only,
+        span,
+    )))
 }
 
-////////////////////////////////////////////////////////////////////////////////
-// Consume
-////////////////////////////////////////////////////////////////////////////////
+/// Consumes a chain of types separated by `->`, like `A -> B -> C`.
+///
+/// Arrows inside angle brackets belong to the types, so `Box<dyn Fn() -> A>`
+/// stays one type. Types with a return type at the top level, like
+/// `fn() -> A`, have to be wrapped in parentheses.
+fn consume_conversion_chain(tokens: &mut TokenIter) -> Result<Vec<TokenStream>, Error> {
+    let mut result = Vec::new();
+    let mut current = TokenStream::new();
+    let mut span = tokens.span();
+    let mut depth = 0;
 
-fn consume_predicate_tree(
-    tokens: &mut TokenIter,
-    errors: &mut Vec<Error>,
-) -> Result<PredicateTree, Error> {
-    if consume_optional_ident(tokens, "for").is_some() {
-        consume_punct(tokens, '<')?;
+    loop {
+        let is_end = tokens.peek().is_none()
+            || depth == 0 && (peek_punct(tokens, ',') || peek_punct(tokens, ';'));
+        let is_arrow = depth == 0 && peek_punct(tokens, '-') && peek2_punct(tokens, '>');
 
-        let mut generic_params = Vec::new();
-        while tokens.peek().is_some() && !peek_punct(tokens, '>') {
-            generic_params.push(consume_generic_param(tokens)?);
+        if is_end || is_arrow {
+            if current.is_empty() {
+                return Err(Error::new_at_span(span, "expected a type expression"));
+            }
+
+            let mut operand = Group::new(Delimiter::None, std::mem::take(&mut current));
+            operand.set_span(span);
+            result.push(TokenTree::Group(operand).into());
+
+            if is_end {
+                return Ok(result);
+            }
+
+            tokens.next();
+            tokens.next();
+            span = tokens.span();
+            continue;
+        }
+
+        if peek_punct(tokens, '-') && peek2_punct(tokens, '>') {
+            current.append(tokens.next().expect("peek ensures a token exists"));
+        } else if peek_punct(tokens, '<') {
+            depth += 1;
+        } else if peek_punct(tokens, '>') {
+            depth -= 1;
+        }
+
+        current.append(tokens.next().expect("peek ensures a token exists"));
+    }
+}
+
+/// Consumes a `fwd(T; &, Box<dyn>): Trait` predicate and desugars it into a
+/// group that asserts `Trait` for each wrapper of `T`.
+fn consume_forwarding_predicate(tokens: &mut TokenIter) -> Result<PredicateTree, Error> {
+    let span = consume_ident(tokens, "fwd")?.span();
+    let parens = consume_delimiter(tokens, Delimiter::Parenthesis)?;
+
+    let inner_tokens = &mut TokenIter::from(parens.stream());
+    let ty_span = inner_tokens.span();
+    let ty = consume_operand(inner_tokens)?;
+
+    let wrappers = if consume_optional_punct(inner_tokens, ';').is_some() {
+        let mut wrappers = Vec::new();
+        while inner_tokens.peek().is_some() {
+            wrappers.push(consume_wrapper(inner_tokens)?);
 
-            if consume_optional_punct(tokens, ',').is_none() {
+            if consume_optional_punct(inner_tokens, ',').is_none() {
                 break;
             }
         }
 
-        consume_punct(tokens, '>')?;
+        wrappers
+    } else {
+        Wrapper::DEFAULT.map(|wrapper| (wrapper, ty_span)).to_vec()
+    };
 
-        if let Some(i) = (1..generic_params.len())
-            .find(|&i| generic_params[i].is_lifetime && !generic_params[i - 1].is_lifetime)
-        {
-            errors.push(Error::new_at_span(
-                generic_params[i].span,
-                "lifetime parameters must be declared prior to type and const parameters",
-            ));
+    if let Some(token) = inner_tokens.peek() {
+        return Err(Error::new_at_span(token.span(), "expected `,`"));
+    }
+
+    consume_punct(tokens, ':')?;
+    let bound = consume_type_expr(tokens)?;
+
+    Ok(PredicateTree::Group(forwarding_group(
+        ty, wrappers, bound, span,
+    )))
+}
+
+/// Consumes one of `&`, `&mut`, `Box`, `Rc`, `Arc` or `Box<dyn>`.
+fn consume_wrapper(tokens: &mut TokenIter) -> Result<(Wrapper, Span), Error> {
+    let span = tokens.span();
+
+    let wrapper = if consume_optional_punct(tokens, '&').is_some() {
+        if consume_optional_ident(tokens, "mut").is_some() {
+            Wrapper::RefMut
+        } else {
+            Wrapper::Ref
         }
+    } else if consume_optional_ident(tokens, "Box").is_some() {
+        if consume_optional_punct(tokens, '<').is_some() {
+            consume_ident(tokens, "dyn")?;
+            consume_punct(tokens, '>')?;
 
-        let where_predicates = consume_optional_where_clause(tokens)?.unwrap_or_default();
+            Wrapper::BoxDyn
+        } else {
+            Wrapper::Box
+        }
+    } else if consume_optional_ident(tokens, "Rc").is_some() {
+        Wrapper::Rc
+    } else if consume_optional_ident(tokens, "Arc").is_some() {
+        Wrapper::Arc
+    } else {
+        return Err(Error::new_at_span(
+            span,
+            "expected one of `&`, `&mut`, `Box`, `Rc`, `Arc` or `Box<dyn>`",
+        ));
+    };
 
-        let braces = consume_delimiter(tokens, Delimiter::Brace)?;
-        let predicates = parse_predicate_list(braces.stream(), errors);
+    Ok((wrapper, span))
+}
 
-        return Ok(PredicateTree::Group(PredicateGroup {
-            generic_params,
-            where_predicates,
-            predicates,
-        }));
+/// Consumes a `collection!(C, item = T, index = usize, skip(debug))`
+/// predicate and desugars it into a group that asserts the std collection
+/// traits for `C`.
+fn consume_collection_predicate(tokens: &mut TokenIter) -> Result<PredicateTree, Error> {
+    let span = consume_ident(tokens, "collection")?.span();
+    consume_punct(tokens, '!')?;
+    let parens = consume_delimiter(tokens, Delimiter::Parenthesis)?;
+
+    let tokens = &mut TokenIter::from(parens.stream());
+    let collection = consume_operand(tokens)?;
+
+    let mut item = None;
+    let mut index = None;
+    let mut skipped = Vec::new();
+    while consume_optional_punct(tokens, ',').is_some() && tokens.peek().is_some() {
+        if consume_optional_ident(tokens, "item").is_some() {
+            consume_punct(tokens, '=')?;
+            item = Some(consume_operand(tokens)?);
+        } else if consume_optional_ident(tokens, "index").is_some() {
+            consume_punct(tokens, '=')?;
+            index = Some(consume_operand(tokens)?);
+        } else if consume_optional_ident(tokens, "skip").is_some() {
+            skipped.extend(consume_ident_list(
+                tokens,
+                "the name of a collection assertion",
+            )?);
+        } else {
+            return Err(Error::new_at_span(
+                tokens.span(),
+                "expected `item = ...`, `index = ...` or `skip(...)`",
+            ));
+        }
     }
 
-    if let Some(where_predicates) = consume_optional_where_clause(tokens)? {
-        let braces = consume_delimiter(tokens, Delimiter::Brace)?;
-        let predicates = parse_predicate_list(braces.stream(), errors);
+    if let Some(token) = tokens.peek() {
+        return Err(Error::new_at_span(token.span(), "expected `,`"));
+    }
 
-        return Ok(PredicateTree::Group(PredicateGroup {
-            generic_params: Vec::new(),
-            where_predicates,
-            predicates,
-        }));
+    let item = item.ok_or_else(|| Error::new_at_span(parens.span(), "expected `item = ...`"))?;
+
+    Ok(PredicateTree::Group(collection_group(
+        collection, item, index, skipped, span,
+    )?))
+}
+
+/// Consumes an `api_guidelines!(T; skip(default); with(error))` predicate
+/// and desugars it into a group that asserts the API Guidelines items for
+/// `T`.
+fn consume_api_guidelines_predicate(tokens: &mut TokenIter) -> Result<PredicateTree, Error> {
+    let span = consume_ident(tokens, "api_guidelines")?.span();
+    consume_punct(tokens, '!')?;
+    let parens = consume_delimiter(tokens, Delimiter::Parenthesis)?;
+
+    let tokens = &mut TokenIter::from(parens.stream());
+    let ty = consume_operand(tokens)?;
+
+    let mut skipped = Vec::new();
+    let mut added = Vec::new();
+    while consume_optional_punct(tokens, ';').is_some() {
+        let names = if consume_optional_ident(tokens, "skip").is_some() {
+            &mut skipped
+        } else if consume_optional_ident(tokens, "with").is_some() {
+            &mut added
+        } else {
+            return Err(Error::new_at_span(
+                tokens.span(),
+                "expected `skip(...)` or `with(...)`",
+            ));
+        };
+
+        names.extend(consume_ident_list(
+            tokens,
+            "the name of an API guideline check",
+        )?);
     }
 
-    Ok(PredicateTree::Predicate(consume_predicate(tokens)?))
+    if let Some(token) = tokens.peek() {
+        return Err(Error::new_at_span(token.span(), "expected `;`"));
+    }
+
+    Ok(PredicateTree::Group(api_guidelines_group(
+        ty, skipped, added, span,
+    )?))
+}
+
+/// Consumes a parenthesized list of identifiers like `(debug, clone)`.
+fn consume_ident_list(tokens: &mut TokenIter, expected: &str) -> Result<Vec<Ident>, Error> {
+    let parens = consume_delimiter(tokens, Delimiter::Parenthesis)?;
+
+    split_punctuated(parens.stream(), ',')
+        .into_iter()
+        .map(|name| {
+            let name = name.into_iter().collect::<Vec<_>>();
+            match name.as_slice() {
+                [TokenTree::Ident(ident)] => Ok(ident.clone()),
+                _ => Err(Error::new_at_span(
+                    name.first().map_or(parens.span(), TokenTree::span),
+                    format!("expected {expected}"),
+                )),
+            }
+        })
+        .collect()
+}
+
+/// Consumes a `T in [u8, u16] { ... }` or `const N in 0..=32 { ... }` group
+/// after its `for` keyword.
+///
+/// The group is desugared into a group that contains a copy of the body for
+/// each element of the list, where each copy has the element substituted for
+/// the name.
+fn consume_iteration_group(
+    tokens: &mut TokenIter,
+    span: Span,
+    errors: &mut Vec<Error>,
+) -> Result<PredicateTree, Error> {
+    let is_const = consume_optional_ident(tokens, "const").is_some();
+
+    let name = consume_any_ident(tokens)?;
+    consume_ident(tokens, "in")?;
+
+    let elements = if is_const {
+        consume_const_values(tokens)?
+            .into_iter()
+            .map(|value| ListElement {
+                value,
+                attrs: Vec::new(),
+            })
+            .collect()
+    } else if let Some(braces) = consume_optional_delimiter(tokens, Delimiter::Brace) {
+        consume_type_sets(&braces)?
+    } else {
+        let brackets = consume_delimiter(tokens, Delimiter::Bracket)?;
+
+        consume_type_list(&mut brackets.stream().into())?
+    };
+
+    let where_predicates = consume_optional_where_clause(tokens)?.unwrap_or_default();
+
+    let braces = consume_delimiter(tokens, Delimiter::Brace)?;
+    let body = PredicateGroup {
+        where_predicates,
+        ..PredicateGroup::new(parse_predicate_list(braces.stream(), errors), span)
+    };
+
+    let predicates = elements
+        .into_iter()
+        .map(|element| {
+            let span = element.value.span();
+
+            let mut instance = body.clone();
+            instance.span = span;
+            instance.attrs = element.attrs;
+
+            let substitution = Substitution {
+                name: name.clone(),
+                is_lifetime: false,
+                replacement: element.value.into(),
+            };
+            substitution.apply_to_group_contents(&mut instance);
+
+            // Errors point at the element and the compiler's "required by a
+            // bound" note points at the predicate.
+            instance.respan_predicates(span);
+
+            PredicateTree::Group(instance)
+        })
+        .collect();
+
+    Ok(PredicateTree::Group(PredicateGroup::new(predicates, span)))
+}
+
+/// Desugars a group with a `..Ts` parameter into a group that contains a
+/// copy of the group for each arity, where each copy declares its own type
+/// parameters `Ts0`, `Ts1` and so on.
+fn expand_variadic_group(
+    group: PredicateGroup,
+    variadic_param: VariadicParam,
+    names: &mut Names,
+) -> PredicateTree {
+    let max_arity = variadic_param.arities.iter().copied().max().unwrap_or(0);
+    let items = (0..max_arity)
+        .map(|i| {
+            let name = names.fresh(&format!("{}{i}", variadic_param.name));
+            Ident::new(&name, variadic_param.span)
+        })
+        .collect::<Vec<_>>();
+
+    let predicates = variadic_param
+        .arities
+        .iter()
+        .map(|&arity| {
+            let mut instance = group.clone();
+
+            let expansion = VariadicExpansion {
+                name: variadic_param.name.clone(),
+                items: items[..arity].to_vec(),
+            };
+            expansion.apply_to_group_contents(&mut instance);
+
+            instance.generic_params.extend(
+                items[..arity]
+                    .iter()
+                    .map(|item| GenericParam::new_type(item.clone(), variadic_param.bound.clone())),
+            );
+
+            PredicateTree::Group(instance)
+        })
+        .collect();
+
+    PredicateTree::Group(PredicateGroup::new(predicates, group.span))
 }
 
 fn consume_predicate(tokens: &mut TokenIter) -> Result<Predicate, Error> {
@@ -99,16 +1294,16 @@ fn consume_predicate(tokens: &mut TokenIter) -> Result<Predicate, Error> {
     let bound = consume_optional_type_expr(tokens).unwrap_or_default();
 
     Ok(Predicate {
-        left_side,
-        bound,
-        span,
         unsized_left_side,
+        ..Predicate::new(left_side, bound, span)
     })
 }
 
 fn consume_generic_param(tokens: &mut TokenIter) -> Result<GenericParam, Error> {
     let mut result;
 
+    let is_const = peek_ident(tokens, "const");
+
     if let Some(lifetime_prefix) = consume_optional_punct(tokens, '\'') {
         let lifetime_prefix_span = lifetime_prefix.span();
         let mut lifetime_prefix = Punct::new('\'', Spacing::Joint);
@@ -119,9 +1314,10 @@ fn consume_generic_param(tokens: &mut TokenIter) -> Result<GenericParam, Error>
         result = GenericParam {
             tokens: quote! { #lifetime_prefix #name },
             inline_arg: quote! { #lifetime_prefix #name },
-            marker_type: quote! { &#lifetime_prefix #name () }.into_iter().collect(),
             is_lifetime: true,
             span: name.span(),
+            name,
+            witness: None,
         };
     } else if let Some(const_prefix) = consume_optional_ident(tokens, "const") {
         let name = consume_any_ident(tokens)?;
@@ -129,20 +1325,13 @@ fn consume_generic_param(tokens: &mut TokenIter) -> Result<GenericParam, Error>
         result = GenericParam {
             tokens: quote! { #const_prefix #name },
             inline_arg: quote! { #name },
-            marker_type: quote! { () }.into_iter().collect(),
             is_lifetime: false,
             span: name.span(),
+            name,
+            witness: None,
         };
     } else {
-        let name = consume_any_ident(tokens)?;
-
-        result = GenericParam {
-            tokens: quote! { #name },
-            inline_arg: quote! { #name },
-            marker_type: quote! { #name },
-            is_lifetime: false,
-            span: name.span(),
-        };
+        result = GenericParam::new_type(consume_any_ident(tokens)?, None);
     }
 
     if let Some(bound_prefix) = consume_optional_punct(tokens, ':') {
@@ -151,9 +1340,226 @@ fn consume_generic_param(tokens: &mut TokenIter) -> Result<GenericParam, Error>
         result.tokens.extend(quote! { #bound_prefix #bound });
     }
 
+    if let Some(witness_prefix) = consume_optional_punct(tokens, '=') {
+        if result.is_lifetime {
+            return Err(Error::new_at_span(
+                witness_prefix.span(),
+                "lifetime parameters can't have witnesses, they are always inferred",
+            ));
+        }
+
+        let witness = consume_type_expr(tokens)?;
+
+        result.witness = Some(if is_const {
+            quote! { { #witness } }
+        } else {
+            witness
+        });
+    }
+
+    Ok(result)
+}
+
+/// Consumes a comma separated list of operands up to a `;`.
+fn consume_operands(tokens: &mut TokenIter) -> Result<Vec<TokenStream>, Error> {
+    let mut result = Vec::new();
+
+    while tokens.peek().is_some() && !peek_punct(tokens, ';') {
+        result.push(consume_operand(tokens)?);
+
+        if consume_optional_punct(tokens, ',').is_none() {
+            break;
+        }
+    }
+
+    Ok(result)
+}
+
+/// Consumes a type and wraps it in an invisible group so that it stays a
+/// single type when it is borrowed or used as a generic argument.
+fn consume_operand(tokens: &mut TokenIter) -> Result<TokenStream, Error> {
+    let span = tokens.span();
+
+    let mut operand = Group::new(Delimiter::None, consume_type_expr(tokens)?);
+    operand.set_span(span);
+
+    Ok(TokenTree::Group(operand).into())
+}
+
+/// Consumes a comma separated list of types and built-in type sets.
+///
+/// Each type is wrapped in an invisible group so that it stays a single type
+/// when substituted into another type, like `&'static str` into `T::Owned`.
+fn consume_type_list(tokens: &mut TokenIter) -> Result<Vec<ListElement>, Error> {
+    let mut result = Vec::new();
+
+    while tokens.peek().is_some() {
+        if let Some(braces) = consume_optional_delimiter(tokens, Delimiter::Brace) {
+            result.extend(consume_type_sets(&braces)?);
+        } else {
+            let span = tokens.span();
+
+            let mut value = Group::new(Delimiter::None, consume_type_expr(tokens)?);
+            value.set_span(span);
+
+            result.push(ListElement {
+                value: TokenTree::Group(value),
+                attrs: Vec::new(),
+            });
+        }
+
+        if consume_optional_punct(tokens, ',').is_none() {
+            break;
+        }
+    }
+
+    if let Some(token) = tokens.peek() {
+        return Err(Error::new_at_span(token.span(), "expected `,`"));
+    }
+
+    Ok(result)
+}
+
+/// Parses the contents of braces like `{integers, floats}` as built-in type
+/// sets and returns their types.
+fn consume_type_sets(braces: &Group) -> Result<Vec<ListElement>, Error> {
+    let tokens = &mut TokenIter::from(braces.stream());
+    let mut result = Vec::new();
+
+    while tokens.peek().is_some() {
+        let name = consume_any_ident(tokens)?;
+
+        result.extend(type_set(&name).ok_or_else(|| {
+            Error::new_at_span(
+                name.span(),
+                format!(
+                    "unknown type set `{name}`, expected one of: {}",
+                    TYPE_SET_NAMES.join(", "),
+                ),
+            )
+        })?);
+
+        if consume_optional_punct(tokens, ',').is_none() {
+            break;
+        }
+    }
+
+    if let Some(token) = tokens.peek() {
+        return Err(Error::new_at_span(token.span(), "expected `,`"));
+    }
+
     Ok(result)
 }
 
+/// Consumes either a list of const generic arguments like `[1, 2, { 2 + 2 }]`
+/// or a range of literals like `0..=32`, and returns each value as a single
+/// token.
+fn consume_const_values(tokens: &mut TokenIter) -> Result<Vec<TokenTree>, Error> {
+    if let Some(brackets) = consume_optional_delimiter(tokens, Delimiter::Bracket) {
+        let mut result = Vec::new();
+
+        for value in split_punctuated(brackets.stream(), ',') {
+            let span = value
+                .clone()
+                .into_iter()
+                .next()
+                .map_or_else(|| brackets.span(), |token| token.span());
+
+            if value.is_empty() {
+                return Err(Error::new_at_span(span, "expected a const value"));
+            }
+
+            result.push(const_arg_token(value, span));
+        }
+
+        return Ok(result);
+    }
+
+    let (values, span) = consume_const_range(tokens)?;
+
+    Ok(values.iter().map(|value| value.to_token(span)).collect())
+}
+
+/// Consumes a range of literals like `0..=32` and returns its values and the
+/// span of its start.
+fn consume_const_range(tokens: &mut TokenIter) -> Result<(Vec<ConstValue>, Span), Error> {
+    let span = tokens.span();
+
+    let mut start = Vec::new();
+    while tokens.peek().is_some() && !(peek_punct(tokens, '.') && peek2_punct(tokens, '.')) {
+        start.push(tokens.next().expect("peek ensures a token exists"));
+    }
+
+    consume_punct(tokens, '.')?;
+    consume_punct(tokens, '.')?;
+    let inclusive = consume_optional_punct(tokens, '=').is_some();
+
+    let mut end = Vec::new();
+    while tokens.peek().is_some()
+        && !peek_ident(tokens, "where")
+        && !peek_delimiter(tokens, Delimiter::Brace)
+        && !peek_punct(tokens, ',')
+        && !peek_punct(tokens, '>')
+    {
+        end.push(tokens.next().expect("peek ensures a token exists"));
+    }
+
+    let expected_literal =
+        "expected an integer, `bool` or `char` literal, or a list of values in brackets";
+    let start = ConstValue::from_tokens(&start)
+        .ok_or_else(|| Error::new_at_span(span, expected_literal))?;
+    let end = ConstValue::from_tokens(&end)
+        .ok_or_else(|| Error::new_at_span(tokens.span(), expected_literal))?;
+
+    let values = ConstValue::range(&start, &end, inclusive)
+        .map_err(|message| Error::new_at_span(span, message))?;
+
+    Ok((values, span))
+}
+
+/// Consumes a `..Ts: Bound; 0..=12` or `..Ts: Bound; [1, 2, 4]` parameter
+/// after its `..`.
+fn consume_variadic_param(tokens: &mut TokenIter) -> Result<VariadicParam, Error> {
+    let name = consume_any_ident(tokens)?;
+
+    let bound = if consume_optional_punct(tokens, ':').is_some() {
+        Some(consume_type_expr(tokens)?)
+    } else {
+        None
+    };
+
+    consume_punct(tokens, ';')?;
+
+    let (values, span) =
+        if let Some(brackets) = consume_optional_delimiter(tokens, Delimiter::Bracket) {
+            let values = split_punctuated(brackets.stream(), ',')
+                .into_iter()
+                .map(|value| ConstValue::from_tokens(&value.into_iter().collect::<Vec<_>>()))
+                .collect::<Option<Vec<_>>>()
+                .ok_or_else(|| Error::new_at_span(brackets.span(), "expected integer literals"))?;
+
+            (values, brackets.span())
+        } else {
+            consume_const_range(tokens)?
+        };
+
+    let arities = values
+        .into_iter()
+        .map(|value| match value {
+            ConstValue::Int(arity, _) => usize::try_from(arity).ok(),
+            ConstValue::Bool(_) | ConstValue::Char(_) => None,
+        })
+        .collect::<Option<Vec<_>>>()
+        .ok_or_else(|| Error::new_at_span(span, "expected a range of non-negative integers"))?;
+
+    Ok(VariadicParam {
+        span: name.span(),
+        name,
+        bound,
+        arities,
+    })
+}
+
 fn consume_type_expr(tokens: &mut TokenIter) -> Result<TokenStream, Error> {
     consume_optional_type_expr(tokens)
         .ok_or_else(|| Error::new_at_span(tokens.span(), "expected a type expression"))
@@ -177,6 +1583,11 @@ fn consume_any_ident(tokens: &mut TokenIter) -> Result<Ident, Error> {
         .ok_or_else(|| Error::new_at_span(tokens.span(), "expected an identifier"))
 }
 
+fn consume_ident(tokens: &mut TokenIter, expected: &str) -> Result<Ident, Error> {
+    consume_optional_ident(tokens, expected)
+        .ok_or_else(|| Error::new_at_span(tokens.span(), format!("expected `{expected}`")))
+}
+
 fn consume_punct(tokens: &mut TokenIter, expected: char) -> Result<Punct, Error> {
     consume_optional_punct(tokens, expected)
         .ok_or_else(|| Error::new_at_span(tokens.span(), format!("expected `{expected}`")))
@@ -216,6 +1627,12 @@ fn consume_optional_type_expr(tokens: &mut TokenIter) -> Option<TokenStream> {
             continue;
         }
 
+        if peek_punct(tokens, '-') && peek2_punct(tokens, '>') {
+            result.append(tokens.next().expect("peek ensures a token exists"));
+            result.append(tokens.next().expect("an arrow has two tokens"));
+            continue;
+        }
+
         if depth <= 0
             && (peek_punct(tokens, ',')
                 || peek_punct(tokens, ';')
@@ -321,6 +1738,14 @@ fn peek_punct(tokens: &TokenIter, expected: char) -> bool {
     }
 }
 
+fn peek2_delimiter(tokens: &TokenIter, expected: Delimiter) -> bool {
+    if let Some(TokenTree::Group(token)) = tokens.peek2() {
+        token.delimiter() == expected
+    } else {
+        false
+    }
+}
+
 fn peek2_punct(tokens: &TokenIter, expected: char) -> bool {
     if let Some(TokenTree::Punct(token)) = tokens.peek2() {
         token.as_char() == expected
@@ -328,3 +1753,34 @@ fn peek2_punct(tokens: &TokenIter, expected: char) -> bool {
         false
     }
 }
+
+////////////////////////////////////////////////////////////////////////////////
+// Split
+////////////////////////////////////////////////////////////////////////////////
+
+/// Splits tokens at each `separator`, not including an empty trailing
+/// segment.
+///
+/// Unlike type expressions, the segments may contain any tokens.
+fn split_punctuated(tokens: TokenStream, separator: char) -> Vec<TokenStream> {
+    let mut result = vec![TokenStream::new()];
+
+    for token in tokens {
+        if let TokenTree::Punct(punct) = &token
+            && punct.as_char() == separator
+        {
+            result.push(TokenStream::new());
+        } else {
+            result
+                .last_mut()
+                .expect("result is never empty")
+                .append(token);
+        }
+    }
+
+    if result.last().is_some_and(TokenStream::is_empty) {
+        result.pop();
+    }
+
+    result
+}
@@ -0,0 +1,113 @@
+use proc_macro2::{Delimiter, Spacing, TokenStream, TokenTree};
+
+/// Formats tokens the way rustfmt would format a type or a bound, like
+/// `for<'a> &'a Vec<T>: Clone`.
+///
+/// `TokenStream`'s `Display` puts spaces between most tokens and keeps the
+/// spacing of the input around some punctuation, so the same predicate can be
+/// displayed in different ways. This is used where users read the text.
+pub fn pretty(tokens: &TokenStream) -> String {
+    let mut result = String::new();
+    write_tokens(tokens, &mut result, &mut Prev::Open);
+
+    result
+}
+
+/// Returns the lowercase words of the tokens, like `vec` and `u8` for
+/// `Vec<u8>`, for names that are made from tokens.
+pub fn words(tokens: &TokenStream) -> Vec<String> {
+    tokens
+        .to_string()
+        .split(|c: char| !c.is_ascii_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(|word| word.to_ascii_lowercase())
+        .collect()
+}
+
+/// The kind of the last written token, which decides if a space goes before
+/// the next one.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Prev {
+    Open,
+    Word,
+    Close,
+    Punct(char, Spacing),
+    PathSep,
+}
+
+fn write_tokens(tokens: &TokenStream, result: &mut String, prev: &mut Prev) {
+    for token in tokens.clone() {
+        match token {
+            TokenTree::Group(group) if group.delimiter() == Delimiter::None => {
+                write_tokens(&group.stream(), result, prev);
+            }
+            TokenTree::Group(group) => {
+                let (open, close) = match group.delimiter() {
+                    Delimiter::Parenthesis => ("(", ")"),
+                    Delimiter::Bracket => ("[", "]"),
+                    Delimiter::Brace => ("{ ", " }"),
+                    Delimiter::None => unreachable!(),
+                };
+
+                let is_call = group.delimiter() == Delimiter::Parenthesis
+                    && matches!(*prev, Prev::Word | Prev::Punct('>', _));
+
+                if !is_call && space_after(*prev) {
+                    result.push(' ');
+                }
+
+                result.push_str(open);
+                *prev = Prev::Open;
+                write_tokens(&group.stream(), result, prev);
+                result.push_str(close);
+                *prev = Prev::Close;
+            }
+            TokenTree::Punct(punct) => {
+                let ch = punct.as_char();
+                let spacing = punct.spacing();
+
+                let is_path_sep_start = ch == ':' && spacing == Spacing::Joint;
+                let is_path_sep_end = ch == ':' && *prev == Prev::Punct(':', Spacing::Joint);
+
+                let space_before = match ch {
+                    ',' | ';' | '>' => false,
+                    ':' if is_path_sep_start => {
+                        !matches!(*prev, Prev::Word | Prev::Close | Prev::Punct('>', _))
+                    }
+                    ':' => false,
+                    '<' => *prev != Prev::Word,
+                    _ => true,
+                };
+
+                if space_before && space_after(*prev) {
+                    result.push(' ');
+                }
+
+                result.push(ch);
+                *prev = if is_path_sep_end {
+                    Prev::PathSep
+                } else {
+                    Prev::Punct(ch, spacing)
+                };
+            }
+            TokenTree::Ident(_) | TokenTree::Literal(_) => {
+                if space_after(*prev) {
+                    result.push(' ');
+                }
+
+                result.push_str(&token.to_string());
+                *prev = Prev::Word;
+            }
+        }
+    }
+}
+
+/// Returns whether a space can follow `prev`.
+fn space_after(prev: Prev) -> bool {
+    match prev {
+        Prev::Open | Prev::PathSep => false,
+        Prev::Punct(_, Spacing::Joint) => false,
+        Prev::Punct(ch, Spacing::Alone) => !matches!(ch, '<' | '&' | '?' | '*' | '!' | '\'' | '#'),
+        Prev::Word | Prev::Close => true,
+    }
+}
@@ -0,0 +1,192 @@
+use proc_macro2::{Group, Ident, TokenStream, TokenTree};
+use quote::quote;
+
+use crate::types::{GenericParam, Predicate, PredicateGroup, PredicateTree};
+
+/// A rewrite of the uses of a name in predicate trees.
+///
+/// Nested groups that declare a parameter with the same name shadow the
+/// rewritten name, so the rewrite doesn't reach into them.
+pub trait Rewrite {
+    fn apply_to_tokens(&self, tokens: &TokenStream) -> TokenStream;
+
+    /// Returns true if `param` hides the rewritten name from the rest of its
+    /// group.
+    fn is_shadowed_by(&self, param: &GenericParam) -> bool;
+
+    fn apply_to_trees(&self, trees: &mut [PredicateTree]) {
+        for tree in trees {
+            match tree {
+                PredicateTree::Group(group) => self.apply_to_group(group),
+                PredicateTree::Predicate(predicate) => self.apply_to_predicate(predicate),
+            }
+        }
+    }
+
+    fn apply_to_group(&self, group: &mut PredicateGroup) {
+        if group
+            .generic_params
+            .iter()
+            .any(|param| self.is_shadowed_by(param))
+        {
+            return;
+        }
+
+        self.apply_to_group_contents(group);
+    }
+
+    /// Like `apply_to_group`, but also applies to groups that declare the
+    /// name, including to the declaration itself.
+    fn apply_to_group_contents(&self, group: &mut PredicateGroup) {
+        for param in &mut group.generic_params {
+            self.apply_to_param(param);
+        }
+
+        for predicate in &mut group.where_predicates {
+            self.apply_to_predicate(predicate);
+        }
+
+        self.apply_to_trees(&mut group.predicates);
+    }
+
+    fn apply_to_predicate(&self, predicate: &mut Predicate) {
+        predicate.left_side = self.apply_to_tokens(&predicate.left_side);
+        predicate.bound = self.apply_to_tokens(&predicate.bound);
+    }
+
+    fn apply_to_param(&self, param: &mut GenericParam) {
+        param.tokens = self.apply_to_tokens(&param.tokens);
+        param.inline_arg = self.apply_to_tokens(&param.inline_arg);
+
+        if let Some(witness) = &mut param.witness {
+            *witness = self.apply_to_tokens(witness);
+        }
+    }
+}
+
+/// Replaces every use of a generic parameter name with other tokens.
+#[derive(Debug, Clone)]
+pub struct Substitution {
+    /// The name to replace, without the `'` of lifetimes.
+    pub name: Ident,
+
+    /// Is true if `name` is a lifetime name.
+    ///
+    /// Lifetimes and types live in different namespaces, so `'T` is never a
+    /// use of `T` and `T` is never a use of `'T`.
+    pub is_lifetime: bool,
+
+    /// The tokens that replace each use of the name, including the `'` of
+    /// lifetimes.
+    pub replacement: TokenStream,
+}
+
+/// Expands every `..Ts` into a comma separated list of types.
+///
+/// A comma that directly follows `..Ts` follows each type instead, so that
+/// `(..Ts,)` expands to `()`, `(Ts0,)`, `(Ts0, Ts1,)` and so on.
+#[derive(Debug, Clone)]
+pub struct VariadicExpansion {
+    /// The `Ts` in `..Ts`.
+    pub name: Ident,
+
+    pub items: Vec<Ident>,
+}
+
+impl Rewrite for Substitution {
+    fn apply_to_tokens(&self, tokens: &TokenStream) -> TokenStream {
+        let mut result = Vec::new();
+        let mut is_after_lifetime_prefix = false;
+
+        for token in tokens.clone() {
+            let is_lifetime_prefix =
+                matches!(&token, TokenTree::Punct(punct) if punct.as_char() == '\'');
+
+            match token {
+                TokenTree::Ident(ident)
+                    if ident == self.name && is_after_lifetime_prefix == self.is_lifetime =>
+                {
+                    if self.is_lifetime {
+                        result.pop();
+                    }
+
+                    result.extend(self.replacement.clone());
+                }
+                TokenTree::Group(group) => result
+                    .push(TokenTree::Group(map_group(&group, |stream| {
+                        self.apply_to_tokens(stream)
+                    }))),
+                token => result.push(token),
+            }
+
+            is_after_lifetime_prefix = is_lifetime_prefix;
+        }
+
+        result.into_iter().collect()
+    }
+
+    fn is_shadowed_by(&self, param: &GenericParam) -> bool {
+        param.is_lifetime == self.is_lifetime && param.name == self.name
+    }
+}
+
+impl Rewrite for VariadicExpansion {
+    fn apply_to_tokens(&self, tokens: &TokenStream) -> TokenStream {
+        let tokens = tokens.clone().into_iter().collect::<Vec<_>>();
+        let mut result = TokenStream::new();
+
+        let mut i = 0;
+        while i < tokens.len() {
+            if let [
+                TokenTree::Punct(dot1),
+                TokenTree::Punct(dot2),
+                TokenTree::Ident(name),
+                rest @ ..,
+            ] = &tokens[i..]
+                && dot1.as_char() == '.'
+                && dot2.as_char() == '.'
+                && *name == self.name
+            {
+                let trailing_comma = match rest.first() {
+                    Some(TokenTree::Punct(punct)) if punct.as_char() == ',' => Some(punct),
+                    _ => None,
+                };
+
+                for (item_index, item) in self.items.iter().enumerate() {
+                    result.extend([TokenTree::Ident(item.clone())]);
+
+                    if let Some(comma) = trailing_comma {
+                        result.extend([TokenTree::Punct(comma.clone())]);
+                    } else if item_index + 1 < self.items.len() {
+                        result.extend(quote! { , });
+                    }
+                }
+
+                i += if trailing_comma.is_some() { 4 } else { 3 };
+                continue;
+            }
+
+            result.extend([match &tokens[i] {
+                TokenTree::Group(group) => {
+                    TokenTree::Group(map_group(group, |stream| self.apply_to_tokens(stream)))
+                }
+                token => token.clone(),
+            }]);
+
+            i += 1;
+        }
+
+        result
+    }
+
+    fn is_shadowed_by(&self, param: &GenericParam) -> bool {
+        !param.is_lifetime && param.name == self.name
+    }
+}
+
+fn map_group(group: &Group, f: impl FnOnce(&TokenStream) -> TokenStream) -> Group {
+    let mut result = Group::new(group.delimiter(), f(&group.stream()));
+    result.set_span(group.span());
+
+    result
+}
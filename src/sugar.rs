@@ -0,0 +1,850 @@
+use proc_macro2::{Delimiter, Group, Ident, Span, TokenStream, TokenTree};
+use quote::{quote, quote_spanned};
+
+use crate::{
+    hygiene::Names,
+    types::{Error, GenericParam, Predicate, PredicateGroup, PredicateTree},
+};
+
+////////////////////////////////////////////////////////////////////////////////
+// Operators
+////////////////////////////////////////////////////////////////////////////////
+
+/// The operator traits that `ops(A, B): ...` accepts, with the number of
+/// operands they take.
+///
+/// Compound assignment traits take two operands, but the left side is never
+/// borrowed.
+const OPERATOR_TRAITS: [(&str, usize); 22] = [
+    ("Add", 2),
+    ("Sub", 2),
+    ("Mul", 2),
+    ("Div", 2),
+    ("Rem", 2),
+    ("BitAnd", 2),
+    ("BitOr", 2),
+    ("BitXor", 2),
+    ("Shl", 2),
+    ("Shr", 2),
+    ("AddAssign", 2),
+    ("SubAssign", 2),
+    ("MulAssign", 2),
+    ("DivAssign", 2),
+    ("RemAssign", 2),
+    ("BitAndAssign", 2),
+    ("BitOrAssign", 2),
+    ("BitXorAssign", 2),
+    ("ShlAssign", 2),
+    ("ShrAssign", 2),
+    ("Neg", 1),
+    ("Not", 1),
+];
+
+/// Desugars `ops(A, B) -> Out: Add + SubAssign` into a group that asserts
+/// each operator for every combination of owned and borrowed operands.
+///
+/// Binary operators are asserted for `A op B`, `&A op B`, `A op &B` and
+/// `&A op &B`, unary operators for `op A` and `op &A`, and compound
+/// assignments for `A op= B` and `A op= &B`. The output of every operator
+/// except compound assignments must be `Out`, which defaults to `A`.
+pub fn operator_group(
+    operands: Vec<TokenStream>,
+    output: Option<TokenStream>,
+    traits: Vec<Ident>,
+    span: Span,
+) -> Result<PredicateGroup, Error> {
+    let mut names = Names::new(&quote! { #(#operands)* #output });
+    let lhs_lifetime = GenericParam::new_lifetime(Ident::new(&names.fresh("a"), span));
+    let rhs_lifetime = GenericParam::new_lifetime(Ident::new(&names.fresh("b"), span));
+
+    let lhs = &operands[0];
+    let rhs = operands.get(1);
+    let output = output.unwrap_or_else(|| lhs.clone());
+
+    let borrowed_lhs = borrowed(&lhs_lifetime, lhs);
+    let borrowed_rhs = rhs.map(|rhs| borrowed(&rhs_lifetime, rhs));
+
+    let mut predicates = Vec::new();
+    let mut uses_lhs_lifetime = false;
+    let mut uses_rhs_lifetime = false;
+
+    for name in traits {
+        let Some(&(_, arity)) = OPERATOR_TRAITS
+            .iter()
+            .find(|(trait_name, _)| name == trait_name)
+        else {
+            return Err(Error::new_at_span(
+                name.span(),
+                format!(
+                    "unknown operator trait `{name}`, expected one of: {}",
+                    OPERATOR_TRAITS.map(|(trait_name, _)| trait_name).join(", "),
+                ),
+            ));
+        };
+
+        if arity != operands.len() {
+            return Err(Error::new_at_span(
+                name.span(),
+                format!(
+                    "`{name}` takes {arity} operand{}, but {} {} given",
+                    if arity == 1 { "" } else { "s" },
+                    operands.len(),
+                    if operands.len() == 1 { "was" } else { "were" },
+                ),
+            ));
+        }
+
+        let span = name.span();
+        let path = quote_spanned! { span => ::core::ops::#name };
+        let mut push = |left_side: &TokenStream, bound: TokenStream| {
+            predicates.push(PredicateTree::Predicate(Predicate::new(
+                left_side.clone(),
+                bound,
+                span,
+            )));
+        };
+
+        match rhs.zip(borrowed_rhs.as_ref()) {
+            None => {
+                uses_lhs_lifetime = true;
+
+                for left_side in [lhs, &borrowed_lhs] {
+                    push(left_side, quote! { #path<Output = #output> });
+                }
+            }
+            Some((rhs, borrowed_rhs)) => {
+                uses_rhs_lifetime = true;
+
+                if name.to_string().ends_with("Assign") {
+                    for right_side in [rhs, borrowed_rhs] {
+                        push(lhs, quote! { #path<#right_side> });
+                    }
+                } else {
+                    uses_lhs_lifetime = true;
+
+                    for left_side in [lhs, &borrowed_lhs] {
+                        for right_side in [rhs, borrowed_rhs] {
+                            push(left_side, quote! { #path<#right_side, Output = #output> });
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    let mut generic_params = Vec::new();
+    let mut where_predicates = Vec::new();
+
+    for (is_used, lifetime, operand) in [
+        (uses_lhs_lifetime, lhs_lifetime, Some(lhs)),
+        (uses_rhs_lifetime, rhs_lifetime, rhs),
+    ] {
+        if let (true, Some(operand)) = (is_used, operand) {
+            where_predicates.push(Predicate::new(
+                operand.clone(),
+                lifetime.inline_arg.clone(),
+                span,
+            ));
+            generic_params.push(lifetime);
+        }
+    }
+
+    Ok(PredicateGroup {
+        generic_params,
+        where_predicates,
+        ..PredicateGroup::new(predicates, span)
+    })
+}
+
+fn borrowed(lifetime: &GenericParam, ty: &TokenStream) -> TokenStream {
+    let lifetime = &lifetime.inline_arg;
+
+    quote! { &#lifetime #ty }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+// Trait Relations
+////////////////////////////////////////////////////////////////////////////////
+
+/// Desugars one direction of `trait A => B` into a group that asserts that
+/// every implementor of `A` implements `B`.
+///
+/// The implementor is a hidden `?Sized` type parameter named after the trait,
+/// like `ImplCopy`, and the assertion is labelled like `CopyImpliesClone`, so
+/// that errors name the traits.
+pub fn implication_group(left: TokenStream, right: TokenStream, span: Span) -> PredicateGroup {
+    let mut names = Names::new(&quote! { #left #right });
+    let left_name = trait_name(&left);
+    let right_name = trait_name(&right);
+
+    let implementor = Ident::new(&names.fresh(&format!("Impl{left_name}")), span);
+    let label = Ident::new(
+        &names.fresh(&format!("{left_name}Implies{right_name}")),
+        span,
+    );
+
+    let predicate = Predicate {
+        unsized_left_side: true,
+        label: Some(label),
+        ..Predicate::new(quote! { #implementor }, right, span)
+    };
+
+    PredicateGroup {
+        generic_params: vec![GenericParam::new_type(
+            implementor,
+            Some(quote! { ?Sized + #left }),
+        )],
+        ..PredicateGroup::new(vec![PredicateTree::Predicate(predicate)], span)
+    }
+}
+
+/// Returns the name of the first trait in a bound list, like `Ord` for
+/// `core::cmp::Ord + Send`, or `Trait` if there is none.
+fn trait_name(bounds: &TokenStream) -> String {
+    let mut result = None;
+
+    for token in bounds.clone() {
+        match token {
+            TokenTree::Ident(ident) => result = Some(ident.to_string()),
+            TokenTree::Punct(punct) if matches!(punct.as_char(), '<' | '+') => break,
+            TokenTree::Punct(punct) if punct.as_char() == '\'' => break,
+            TokenTree::Group(_) => break,
+            TokenTree::Punct(_) | TokenTree::Literal(_) => {}
+        }
+    }
+
+    result.unwrap_or_else(|| "Trait".to_string())
+}
+
+////////////////////////////////////////////////////////////////////////////////
+// Comparisons
+////////////////////////////////////////////////////////////////////////////////
+
+/// Desugars `compare!(A, B, C; ord; except(A == C))` into a group that
+/// asserts `PartialEq`, and `PartialOrd` if `is_ord` is true, between every
+/// ordered pair of distinct types except the listed ones.
+///
+/// Elided lifetimes like the ones in `&str` and `Cow<'_, str>` are named by a
+/// lifetime parameter of the group, since they aren't allowed in bounds.
+pub fn comparison_group(
+    types: Vec<TokenStream>,
+    is_ord: bool,
+    exceptions: Vec<(TokenStream, TokenStream, Span)>,
+    span: Span,
+) -> Result<PredicateGroup, Error> {
+    let mut names = Names::new(&quote! { #(#types)* });
+    let lifetime = GenericParam::new_lifetime(Ident::new(&names.fresh("a"), span));
+
+    let traits = if is_ord {
+        vec![
+            quote! { ::core::cmp::PartialEq },
+            quote! { ::core::cmp::PartialOrd },
+        ]
+    } else {
+        vec![quote! { ::core::cmp::PartialEq }]
+    };
+
+    let mut is_excepted = vec![false; exceptions.len()];
+    let mut predicates = Vec::new();
+    let mut uses_lifetime = false;
+
+    for (i, left) in types.iter().enumerate() {
+        for (j, right) in types.iter().enumerate() {
+            if i == j {
+                continue;
+            }
+
+            if let Some(k) = exceptions
+                .iter()
+                .position(|(excepted_left, excepted_right, _)| {
+                    excepted_left.to_string() == left.to_string()
+                        && excepted_right.to_string() == right.to_string()
+                })
+            {
+                is_excepted[k] = true;
+                continue;
+            }
+
+            let (left_side, left_has_elided) = name_elided_lifetimes(left, &lifetime.inline_arg);
+            let (right_side, right_has_elided) = name_elided_lifetimes(right, &lifetime.inline_arg);
+            uses_lifetime |= left_has_elided || right_has_elided;
+
+            let span = left
+                .clone()
+                .into_iter()
+                .next()
+                .map_or(span, |token| token.span());
+
+            for path in &traits {
+                predicates.push(PredicateTree::Predicate(Predicate {
+                    unsized_left_side: true,
+                    ..Predicate::new(left_side.clone(), quote! { #path<#right_side> }, span)
+                }));
+            }
+        }
+    }
+
+    if let Some(k) = is_excepted.iter().position(|&is_excepted| !is_excepted) {
+        let (left, right, span) = &exceptions[k];
+
+        return Err(Error::new_at_span(
+            *span,
+            format!("`{left} == {right}` isn't one of the compared pairs"),
+        ));
+    }
+
+    Ok(PredicateGroup {
+        generic_params: if uses_lifetime {
+            vec![lifetime]
+        } else {
+            Vec::new()
+        },
+        ..PredicateGroup::new(predicates, span)
+    })
+}
+
+////////////////////////////////////////////////////////////////////////////////
+// Conversions
+////////////////////////////////////////////////////////////////////////////////
+
+/// Desugars `convert!(A -> B -> C; transitive)` into a group that asserts
+/// each conversion of the chains.
+///
+/// Each arrow `A -> B` asserts `B: From<A>`, `A: Into<B>` and, unless
+/// `is_forward_only` is true, `A: TryFrom<B>`. If `is_transitive` is true,
+/// `From` and `Into` are also asserted between every pair of types that are
+/// connected through more than one arrow.
+pub fn conversion_group(
+    chains: Vec<Vec<TokenStream>>,
+    is_transitive: bool,
+    is_forward_only: bool,
+    span: Span,
+) -> PredicateGroup {
+    let mut names = Names::new(&quote! { #(#(#chains)*)* });
+    let lifetime = GenericParam::new_lifetime(Ident::new(&names.fresh("a"), span));
+
+    // The types of the graph, and the arrows between them as indices.
+    let mut types = Vec::<TokenStream>::new();
+    let mut arrows = Vec::new();
+
+    for chain in chains {
+        let indices = chain
+            .into_iter()
+            .map(|ty| {
+                let text = ty.to_string();
+
+                types
+                    .iter()
+                    .position(|other| other.to_string() == text)
+                    .unwrap_or_else(|| {
+                        types.push(ty);
+                        types.len() - 1
+                    })
+            })
+            .collect::<Vec<_>>();
+
+        for pair in indices.windows(2) {
+            if pair[0] != pair[1] && !arrows.contains(&(pair[0], pair[1])) {
+                arrows.push((pair[0], pair[1]));
+            }
+        }
+    }
+
+    let mut jumps = Vec::new();
+    if is_transitive {
+        for start in 0..types.len() {
+            let mut reachable = vec![start];
+            let mut i = 0;
+
+            while let Some(&from) = reachable.get(i) {
+                for &(_, to) in arrows.iter().filter(|(arrow_from, _)| *arrow_from == from) {
+                    if !reachable.contains(&to) {
+                        reachable.push(to);
+                    }
+                }
+
+                i += 1;
+            }
+
+            jumps.extend(
+                reachable
+                    .into_iter()
+                    .skip(1)
+                    .filter(|&end| !arrows.contains(&(start, end)))
+                    .map(|end| (start, end)),
+            );
+        }
+    }
+
+    let mut uses_lifetime = false;
+    let types = types
+        .iter()
+        .map(|ty| {
+            let (ty, has_elided) = name_elided_lifetimes(ty, &lifetime.inline_arg);
+            uses_lifetime |= has_elided;
+
+            ty
+        })
+        .collect::<Vec<_>>();
+
+    let mut predicates = Vec::new();
+    let mut push = |left_side: &TokenStream, bound: TokenStream| {
+        let span = left_side
+            .clone()
+            .into_iter()
+            .next()
+            .map_or(span, |token| token.span());
+
+        predicates.push(PredicateTree::Predicate(Predicate::new(
+            left_side.clone(),
+            bound,
+            span,
+        )));
+    };
+
+    for &(from, to) in arrows.iter().chain(&jumps) {
+        let (source, target) = (&types[from], &types[to]);
+
+        push(target, quote! { ::core::convert::From<#source> });
+        push(source, quote! { ::core::convert::Into<#target> });
+
+        if !is_forward_only && !jumps.contains(&(from, to)) {
+            push(source, quote! { ::core::convert::TryFrom<#target> });
+        }
+    }
+
+    PredicateGroup {
+        generic_params: if uses_lifetime {
+            vec![lifetime]
+        } else {
+            Vec::new()
+        },
+        ..PredicateGroup::new(predicates, span)
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+// Forwarding
+////////////////////////////////////////////////////////////////////////////////
+
+/// A wrapper that `fwd(T; ...): Trait` asserts forwards `Trait` from `T`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Wrapper {
+    /// `&T`
+    Ref,
+    /// `&mut T`
+    RefMut,
+    /// `Box<T>`
+    Box,
+    /// `Rc<T>`
+    Rc,
+    /// `Arc<T>`
+    Arc,
+    /// `Box<dyn Trait>`, which only exists for dyn-compatible traits.
+    BoxDyn,
+}
+
+impl Wrapper {
+    /// The wrappers that `fwd(T): Trait` uses when none are listed.
+    pub const DEFAULT: [Self; 5] = [Self::Ref, Self::RefMut, Self::Box, Self::Rc, Self::Arc];
+}
+
+/// Desugars `fwd(T; &, Box): Trait` into a group that asserts `Trait` for
+/// each wrapper of `T`.
+///
+/// Borrowing wrappers get a lifetime parameter that `T` outlives, so that
+/// the group also works for generic parameters of outer groups.
+pub fn forwarding_group(
+    ty: TokenStream,
+    wrappers: Vec<(Wrapper, Span)>,
+    bound: TokenStream,
+    span: Span,
+) -> PredicateGroup {
+    let mut names = Names::new(&quote! { #ty #bound });
+    let lifetime = GenericParam::new_lifetime(Ident::new(&names.fresh("a"), span));
+    let lifetime_arg = &lifetime.inline_arg;
+
+    let predicates = wrappers
+        .iter()
+        .map(|&(wrapper, span)| {
+            let left_side = match wrapper {
+                Wrapper::Ref => quote! { &#lifetime_arg #ty },
+                Wrapper::RefMut => quote! { &#lifetime_arg mut #ty },
+                Wrapper::Box => quote_spanned! { span => ::std::boxed::Box<#ty> },
+                Wrapper::Rc => quote_spanned! { span => ::std::rc::Rc<#ty> },
+                Wrapper::Arc => quote_spanned! { span => ::std::sync::Arc<#ty> },
+                Wrapper::BoxDyn => quote_spanned! { span => ::std::boxed::Box<dyn #bound> },
+            };
+
+            PredicateTree::Predicate(Predicate::new(left_side, bound.clone(), span))
+        })
+        .collect();
+
+    let mut group = PredicateGroup::new(predicates, span);
+
+    if wrappers
+        .iter()
+        .any(|(wrapper, _)| matches!(wrapper, Wrapper::Ref | Wrapper::RefMut))
+    {
+        group
+            .where_predicates
+            .push(Predicate::new(ty, lifetime.inline_arg.clone(), span));
+        group.generic_params.push(lifetime);
+    }
+
+    group
+}
+
+////////////////////////////////////////////////////////////////////////////////
+// Collections
+////////////////////////////////////////////////////////////////////////////////
+
+/// The names of the assertions that `collection!(...)` makes, which can be
+/// skipped with `skip(...)`.
+pub const COLLECTION_ITEMS: [&str; 12] = [
+    "into_iter",
+    "into_iter_ref",
+    "into_iter_mut",
+    "from_iter",
+    "extend",
+    "extend_ref",
+    "default",
+    "debug",
+    "clone",
+    "partial_eq",
+    "index",
+    "index_mut",
+];
+
+/// Desugars `collection!(C, item = T, index = I, skip(...))` into a group
+/// that asserts the std collection traits for `C`.
+///
+/// The assertions that depend on a trait of the items, like `Debug` and
+/// `Extend<&T>`, are put in groups that require that trait of `T` if `T` is
+/// generic, and are asserted directly if it is concrete, where `skip(...)`
+/// turns them off. `Index` and `IndexMut` are only asserted if an index type
+/// is given.
+pub fn collection_group(
+    collection: TokenStream,
+    item: TokenStream,
+    index: Option<TokenStream>,
+    skipped: Vec<Ident>,
+    span: Span,
+) -> Result<PredicateGroup, Error> {
+    if let Some(name) = skipped
+        .iter()
+        .find(|name| !COLLECTION_ITEMS.iter().any(|item| *name == item))
+    {
+        return Err(Error::new_at_span(
+            name.span(),
+            format!(
+                "unknown collection assertion `{name}`, expected one of: {}",
+                COLLECTION_ITEMS.join(", "),
+            ),
+        ));
+    }
+
+    let mut names = Names::new(&quote! { #collection #item #index });
+    let lifetime = GenericParam::new_lifetime(Ident::new(&names.fresh("a"), span));
+    let lifetime_arg = &lifetime.inline_arg;
+
+    let mut assertions = vec![
+        (
+            "into_iter",
+            None,
+            quote! { #collection },
+            quote! { ::core::iter::IntoIterator<Item = #item> },
+        ),
+        (
+            "into_iter_ref",
+            None,
+            quote! { &#lifetime_arg #collection },
+            quote! { ::core::iter::IntoIterator<Item = &#lifetime_arg #item> },
+        ),
+        (
+            "into_iter_mut",
+            None,
+            quote! { &#lifetime_arg mut #collection },
+            quote! { ::core::iter::IntoIterator<Item = &#lifetime_arg mut #item> },
+        ),
+        (
+            "from_iter",
+            None,
+            quote! { #collection },
+            quote! { ::core::iter::FromIterator<#item> },
+        ),
+        (
+            "extend",
+            None,
+            quote! { #collection },
+            quote! { ::core::iter::Extend<#item> },
+        ),
+        (
+            "extend_ref",
+            Some(quote! { ::core::marker::Copy }),
+            quote! { #collection },
+            quote! { ::core::iter::Extend<&#lifetime_arg #item> },
+        ),
+        (
+            "default",
+            None,
+            quote! { #collection },
+            quote! { ::core::default::Default },
+        ),
+        (
+            "debug",
+            Some(quote! { ::core::fmt::Debug }),
+            quote! { #collection },
+            quote! { ::core::fmt::Debug },
+        ),
+        (
+            "clone",
+            Some(quote! { ::core::clone::Clone }),
+            quote! { #collection },
+            quote! { ::core::clone::Clone },
+        ),
+        (
+            "partial_eq",
+            Some(quote! { ::core::cmp::PartialEq }),
+            quote! { #collection },
+            quote! { ::core::cmp::PartialEq },
+        ),
+    ];
+
+    if let Some(index) = &index {
+        assertions.extend([
+            (
+                "index",
+                None,
+                quote! { #collection },
+                quote! { ::core::ops::Index<#index, Output = #item> },
+            ),
+            (
+                "index_mut",
+                None,
+                quote! { #collection },
+                quote! { ::core::ops::IndexMut<#index> },
+            ),
+        ]);
+    }
+
+    let mut predicates = Vec::new();
+    let mut uses_lifetime = false;
+
+    for (name, item_bound, left_side, bound) in assertions {
+        if skipped.iter().any(|skipped| skipped == name) {
+            continue;
+        }
+
+        uses_lifetime |= matches!(name, "into_iter_ref" | "into_iter_mut" | "extend_ref");
+
+        let predicate = PredicateTree::Predicate(Predicate::new(left_side, bound, span));
+
+        let Some(item_bound) = item_bound else {
+            predicates.push(predicate);
+            continue;
+        };
+
+        predicates.push(PredicateTree::Group(PredicateGroup {
+            where_predicates: vec![Predicate::new(item.clone(), item_bound, span)],
+            unconditional_if_concrete: true,
+            ..PredicateGroup::new(vec![predicate], span)
+        }));
+    }
+
+    let mut group = PredicateGroup::new(predicates, span);
+
+    if uses_lifetime {
+        for ty in [collection, item] {
+            group
+                .where_predicates
+                .push(Predicate::new(ty, lifetime.inline_arg.clone(), span));
+        }
+
+        group.generic_params.push(lifetime);
+    }
+
+    Ok(group)
+}
+
+////////////////////////////////////////////////////////////////////////////////
+// API Guidelines
+////////////////////////////////////////////////////////////////////////////////
+
+/// The checks that `api_guidelines!(...)` makes, with the guideline they
+/// belong to and whether they are made unless turned off.
+const API_GUIDELINE_ITEMS: [(&str, &str, bool); 9] = [
+    ("debug", "C_DEBUG", true),
+    ("clone", "C_COMMON_TRAITS", true),
+    ("eq", "C_COMMON_TRAITS", true),
+    ("partial_ord", "C_COMMON_TRAITS", true),
+    ("hash", "C_COMMON_TRAITS", true),
+    ("default", "C_COMMON_TRAITS", true),
+    ("send", "C_SEND_SYNC", true),
+    ("sync", "C_SEND_SYNC", true),
+    ("error", "C_GOOD_ERR", false),
+];
+
+/// Desugars `api_guidelines!(T; skip(default); with(error))` into a group
+/// that asserts the common traits and `Send`/`Sync` items of the Rust API
+/// Guidelines for `T`.
+///
+/// Each assertion is labelled with its guideline, like `C_COMMON_TRAITS`, so
+/// that errors name the guideline instead of a generated helper struct.
+pub fn api_guidelines_group(
+    ty: TokenStream,
+    skipped: Vec<Ident>,
+    added: Vec<Ident>,
+    span: Span,
+) -> Result<PredicateGroup, Error> {
+    if let Some(name) = skipped
+        .iter()
+        .chain(&added)
+        .find(|name| !API_GUIDELINE_ITEMS.iter().any(|(item, _, _)| *name == item))
+    {
+        return Err(Error::new_at_span(
+            name.span(),
+            format!(
+                "unknown API guideline check `{name}`, expected one of: {}",
+                API_GUIDELINE_ITEMS.map(|(item, _, _)| item).join(", "),
+            ),
+        ));
+    }
+
+    let mut names = Names::new(&ty);
+    let mut labels = Vec::<(&str, Ident)>::new();
+    let predicate_span = ty
+        .clone()
+        .into_iter()
+        .next()
+        .map_or(span, |token| token.span());
+
+    let mut predicates = Vec::new();
+
+    for (item, guideline, is_default) in API_GUIDELINE_ITEMS {
+        let is_enabled = if skipped.iter().any(|name| name == item) {
+            false
+        } else {
+            is_default || added.iter().any(|name| name == item)
+        };
+
+        if !is_enabled {
+            continue;
+        }
+
+        let bounds = match item {
+            "debug" => vec![quote! { ::core::fmt::Debug }],
+            "clone" => vec![quote! { ::core::clone::Clone }],
+            "eq" => vec![quote! { ::core::cmp::Eq }],
+            "partial_ord" => vec![quote! { ::core::cmp::PartialOrd }],
+            "hash" => vec![quote! { ::core::hash::Hash }],
+            "default" => vec![quote! { ::core::default::Default }],
+            "send" => vec![quote! { ::core::marker::Send }],
+            "sync" => vec![quote! { ::core::marker::Sync }],
+            "error" => vec![
+                quote! {
+                    ::std::error::Error + ::core::marker::Send + ::core::marker::Sync + 'static
+                },
+                quote! { ::core::fmt::Display },
+            ],
+            _ => unreachable!(),
+        };
+
+        let label = match labels.iter().find(|(name, _)| *name == guideline) {
+            Some((_, label)) => label.clone(),
+            None => {
+                let label = Ident::new(&names.fresh(guideline), predicate_span);
+                labels.push((guideline, label.clone()));
+
+                label
+            }
+        };
+
+        predicates.extend(bounds.into_iter().map(|bound| {
+            PredicateTree::Predicate(Predicate {
+                label: Some(label.clone()),
+                ..Predicate::new(ty.clone(), bound, predicate_span)
+            })
+        }));
+    }
+
+    Ok(PredicateGroup::new(predicates, span))
+}
+
+/// Replaces the elided lifetimes in a type, like the ones in `&T` and
+/// `Cow<'_, T>`, with `lifetime`, and returns whether there were any.
+///
+/// The arguments and return types of `Fn(&T) -> &T` and `fn(&T) -> &T` are
+/// left alone, since their elided lifetimes are higher-ranked.
+fn name_elided_lifetimes(tokens: &TokenStream, lifetime: &TokenStream) -> (TokenStream, bool) {
+    let tokens = tokens.clone().into_iter().collect::<Vec<_>>();
+    let mut result = TokenStream::new();
+    let mut has_elided = false;
+
+    let mut depth = 0;
+    let mut return_type_depth = None;
+
+    for (i, token) in tokens.iter().enumerate() {
+        let previous = i.checked_sub(1).map(|i| &tokens[i]);
+        let next = tokens.get(i + 1);
+
+        if let TokenTree::Punct(punct) = token {
+            match punct.as_char() {
+                '<' => depth += 1,
+                '>' if matches!(previous, Some(TokenTree::Punct(p)) if p.as_char() == '-') => {
+                    return_type_depth = Some(depth);
+                }
+                '>' => depth -= 1,
+                ',' if return_type_depth == Some(depth) => return_type_depth = None,
+                _ => {}
+            }
+
+            if return_type_depth.is_some_and(|return_type_depth| depth < return_type_depth) {
+                return_type_depth = None;
+            }
+        }
+
+        if return_type_depth.is_some() {
+            result.extend([token.clone()]);
+            continue;
+        }
+
+        match token {
+            TokenTree::Punct(punct)
+                if punct.as_char() == '&'
+                    && !matches!(next, Some(TokenTree::Punct(p)) if p.as_char() == '\'') =>
+            {
+                result.extend([token.clone()]);
+                result.extend(lifetime.clone());
+                has_elided = true;
+            }
+            TokenTree::Punct(punct)
+                if punct.as_char() == '\''
+                    && matches!(next, Some(TokenTree::Ident(ident)) if ident == "_") => {}
+            TokenTree::Ident(ident)
+                if ident == "_"
+                    && matches!(previous, Some(TokenTree::Punct(p)) if p.as_char() == '\'') =>
+            {
+                result.extend(lifetime.clone());
+                has_elided = true;
+            }
+            TokenTree::Group(group)
+                if group.delimiter() == Delimiter::Parenthesis
+                    && matches!(previous, Some(TokenTree::Ident(_))) =>
+            {
+                result.extend([token.clone()]);
+            }
+            TokenTree::Group(group) => {
+                let (stream, group_has_elided) = name_elided_lifetimes(&group.stream(), lifetime);
+                has_elided |= group_has_elided;
+
+                let mut new_group = Group::new(group.delimiter(), stream);
+                new_group.set_span(group.span());
+                result.extend([TokenTree::Group(new_group)]);
+            }
+            token => result.extend([token.clone()]),
+        }
+    }
+
+    (result, has_elided)
+}
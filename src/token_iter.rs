@@ -1,6 +1,6 @@
 use proc_macro2::{Span, TokenStream, TokenTree};
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct TokenIter {
     /// The tokens are in reverse order so that pop returns the next token.
     reversed_tokens: Vec<TokenTree>,
@@ -35,6 +35,16 @@ impl TokenIter {
     pub fn span(&self) -> Span {
         self.next_span
     }
+
+    /// Returns the tokens that were consumed since `earlier`, which must be a
+    /// clone of this iterator.
+    pub fn consumed_since(&self, earlier: &Self) -> TokenStream {
+        earlier.reversed_tokens[self.reversed_tokens.len()..]
+            .iter()
+            .rev()
+            .cloned()
+            .collect()
+    }
 }
 
 impl From<TokenStream> for TokenIter {
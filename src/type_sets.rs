@@ -0,0 +1,96 @@
+use proc_macro2::{Delimiter, Group, Ident, Span, TokenStream, TokenTree};
+use quote::{quote, quote_spanned};
+
+use crate::types::ListElement;
+
+/// The names of the built-in type sets, as written between braces.
+pub const TYPE_SET_NAMES: [&str; 6] = [
+    "unsigned",
+    "signed",
+    "integers",
+    "floats",
+    "primitives",
+    "atomics",
+];
+
+const UNSIGNED: [&str; 6] = ["u8", "u16", "u32", "u64", "u128", "usize"];
+const SIGNED: [&str; 6] = ["i8", "i16", "i32", "i64", "i128", "isize"];
+const FLOATS: [&str; 2] = ["f32", "f64"];
+
+/// The atomic types with the `target_has_atomic` value they require.
+///
+/// `AtomicPtr` is left out because it is generic.
+const ATOMICS: [(&str, &str); 11] = [
+    ("AtomicBool", "8"),
+    ("AtomicU8", "8"),
+    ("AtomicI8", "8"),
+    ("AtomicU16", "16"),
+    ("AtomicI16", "16"),
+    ("AtomicU32", "32"),
+    ("AtomicI32", "32"),
+    ("AtomicU64", "64"),
+    ("AtomicI64", "64"),
+    ("AtomicUsize", "ptr"),
+    ("AtomicIsize", "ptr"),
+];
+
+/// Returns the types of a built-in type set like `integers`, or `None` if
+/// there is no set with that name.
+///
+/// Types that only exist on some targets carry the `cfg` attribute that
+/// guards them.
+pub fn type_set(name: &Ident) -> Option<Vec<ListElement>> {
+    let span = name.span();
+
+    let primitive = |ty: &str| {
+        let ty = Ident::new(ty, span);
+        element(
+            quote_spanned! { span => ::core::primitive::#ty },
+            span,
+            Vec::new(),
+        )
+    };
+
+    let result = match name.to_string().as_str() {
+        "unsigned" => UNSIGNED.iter().map(|ty| primitive(ty)).collect(),
+        "signed" => SIGNED.iter().map(|ty| primitive(ty)).collect(),
+        "integers" => UNSIGNED
+            .iter()
+            .chain(&SIGNED)
+            .map(|ty| primitive(ty))
+            .collect(),
+        "floats" => FLOATS.iter().map(|ty| primitive(ty)).collect(),
+        "primitives" => UNSIGNED
+            .iter()
+            .chain(&SIGNED)
+            .chain(&FLOATS)
+            .chain(&["bool", "char"])
+            .map(|ty| primitive(ty))
+            .collect(),
+        "atomics" => ATOMICS
+            .iter()
+            .map(|(ty, width)| {
+                let ty = Ident::new(ty, span);
+
+                element(
+                    quote_spanned! { span => ::core::sync::atomic::#ty },
+                    span,
+                    vec![quote! { #[cfg(target_has_atomic = #width)] }],
+                )
+            })
+            .collect(),
+        _ => return None,
+    };
+
+    Some(result)
+}
+
+fn element(tokens: TokenStream, span: Span, attrs: Vec<TokenStream>) -> ListElement {
+    let mut value = Group::new(Delimiter::None, tokens);
+    value.set_span(span);
+
+    ListElement {
+        value: TokenTree::Group(value),
+        attrs,
+    }
+}
@@ -1,7 +1,9 @@
 use std::cmp::Ordering;
 
-use proc_macro2::{Punct, Spacing, Span, TokenStream};
-use quote::{ToTokens, quote_spanned};
+use proc_macro2::{Ident, Literal, Punct, Spacing, Span, TokenStream, TokenTree};
+use quote::{ToTokens, quote, quote_spanned};
+
+use crate::{lint::Lint, pretty::pretty};
 
 /// Represents either a where-clause predicate:
 ///
@@ -29,7 +31,7 @@ pub enum PredicateTree {
 ///     T: Clone + 'static,
 /// }
 /// ```
-#[derive(Debug, Clone, Default)]
+#[derive(Debug, Clone)]
 pub struct PredicateGroup {
     /// The generic parameters declared using the `for` keyword.
     pub generic_params: Vec<GenericParam>,
@@ -45,6 +47,33 @@ pub struct PredicateGroup {
     /// These are predicate-trees meaning they can themselves be
     /// predicate-groups which allows for nesting.
     pub predicates: Vec<PredicateTree>,
+
+    /// The span of the `for` or `where` keyword that starts the group.
+    pub span: Span,
+
+    /// Attributes like `#[cfg(...)]` that are applied to the items emitted
+    /// for the group and the groups nested in it.
+    pub attrs: Vec<TokenStream>,
+
+    /// Whether the where-predicates that don't mention a generic parameter of
+    /// the context are left out, so that the predicates are asserted without
+    /// them.
+    ///
+    /// The compiler checks such where-predicates where they are written, so
+    /// a false one is an error instead of a condition. Presets use this for
+    /// assertions that depend on a trait of the item type, which are
+    /// conditions for generic items and asserted directly for concrete ones.
+    pub unconditional_if_concrete: bool,
+}
+
+/// A predicate with the generic parameters, where-predicates and attributes
+/// of every group around it, like the generated code asserts it.
+#[derive(Debug, Clone)]
+pub struct ContextPredicate<'a> {
+    pub generic_params: Vec<&'a GenericParam>,
+    pub where_predicates: Vec<&'a Predicate>,
+    pub attrs: Vec<&'a TokenStream>,
+    pub predicate: &'a Predicate,
 }
 
 /// Represents a where-clause predicate like `SomeType: SomeTrait`.
@@ -65,9 +94,24 @@ pub struct Predicate {
     /// This is used to hide a compiler limitation related
     /// dynamically-sized-types and the way `assert_impl` works.
     ///
-    /// Currently this is only true for `dyn ...` left sides. Changing this
-    /// would be a breaking change.
+    /// For plain predicates written by the user this is only true for
+    /// `dyn ...` left sides. Changing this would be a breaking change.
     pub unsized_left_side: bool,
+
+    /// A name for the assertion that errors mention instead of the name of
+    /// the generated check function, like `C_DEBUG`.
+    pub label: Option<Ident>,
+
+    /// Why the assertion is made, as given by `#[reason = "..."]`, which
+    /// errors point to.
+    pub reason: Option<Literal>,
+
+    /// The type parameters that replaced the `_` placeholders of the bound,
+    /// which the compiler has to infer.
+    ///
+    /// Placeholders in the left side are left alone, since it is a generic
+    /// argument where the compiler infers them anyway.
+    pub inferred_params: Vec<Ident>,
 }
 
 /// Represents a generic parameter declaration like `const N: usize`.
@@ -81,23 +125,257 @@ pub struct GenericParam {
     /// This corrosponds to the `N` in `const N: usize`.
     pub inline_arg: TokenStream,
 
-    /// A type that depends on the generic parameter.
+    pub is_lifetime: bool,
+
+    pub span: Span,
+
+    /// The name of the parameter, without the `'` of lifetimes.
+    pub name: Ident,
+
+    /// The concrete generic argument from `T = u8` or `const N: usize = 4`.
     ///
-    /// This is used with `PhantomData` to use all generic parameters in struct
-    /// declarations.
-    pub marker_type: TokenStream,
+    /// When every parameter of a context has a witness, the context is also
+    /// instantiated with the witnesses which proves that its where-clause can
+    /// be satisfied.
+    pub witness: Option<TokenStream>,
+}
 
-    pub is_lifetime: bool,
+/// Represents an element of a `for T in [...]` list.
+#[derive(Debug, Clone)]
+pub struct ListElement {
+    /// The type or const value, as a single token so that it can be
+    /// substituted anywhere.
+    pub value: TokenTree,
+
+    /// Attributes like `#[cfg(...)]` that are required for `value` to exist.
+    pub attrs: Vec<TokenStream>,
+}
+
+/// Represents a variadic parameter declaration like `..Ts: Clone; 0..=12`.
+///
+/// Groups with a variadic parameter are repeated for each arity in the range,
+/// and `..Ts` is expanded into that many fresh type parameters.
+#[derive(Debug, Clone)]
+pub struct VariadicParam {
+    /// The `Ts` in `..Ts`.
+    pub name: Ident,
+
+    /// The bound of each type parameter that `..Ts` expands into.
+    pub bound: Option<TokenStream>,
+
+    pub arities: Vec<usize>,
 
     pub span: Span,
 }
 
+/// What the test function of a top-level predicate tree is named after.
+#[derive(Debug, Clone)]
+pub enum TreeSource {
+    /// The label of the tree, which is the name as it is.
+    Label(Ident),
+
+    /// The tokens of a tree without a label, whose words make up the name.
+    Tokens(TokenStream),
+}
+
+/// Represents an `assert_template!` definition like
+/// `pub numeric<T> { T: Copy }`.
+#[derive(Debug, Clone)]
+pub struct Template {
+    /// Outer attributes like doc comments, which are forwarded to the
+    /// generated macro.
+    pub attrs: Vec<TokenStream>,
+
+    /// Is true if the template starts with `pub`, which exports the generated
+    /// macro from the crate.
+    pub is_exported: bool,
+
+    pub name: Ident,
+
+    /// The type parameters that each instance of the template provides.
+    pub params: Vec<Ident>,
+
+    /// The `assert_impl!` input that is asserted for each instance.
+    pub body: TokenStream,
+}
+
+/// Options set by inner attributes at the start of the macro input, like
+/// `#![allow(empty_group)]`.
+#[derive(Debug, Clone, Default)]
+pub struct Config {
+    /// The lints silenced by `#![allow(...)]`.
+    pub allowed_lints: Vec<Lint>,
+
+    /// Whether `#![test]` was given, which emits a `#[test]` function for
+    /// each top-level predicate or group.
+    pub emit_tests: bool,
+
+    /// The name of the module given by `#![test(mod name)]`, which holds the
+    /// test functions.
+    pub test_module: Option<Ident>,
+
+    /// The visibility and name of the module given by
+    /// `#![descriptors(pub mod name)]`, which lists the asserted predicates.
+    pub descriptors: Option<(TokenStream, Ident)>,
+
+    /// The name of the macro given by `#![docs(name)]`, which expands to the
+    /// documentation of the asserted predicates for `#[doc = name!()]`.
+    pub docs_macro: Option<Ident>,
+
+    /// Whether `#![explain]` was given, which shows the asserted predicates
+    /// with their full generic context as a warning.
+    pub explain: bool,
+
+    /// Whether `#![explain(tokens)]` was given, which also shows the
+    /// generated code.
+    pub explain_tokens: bool,
+}
+
 #[derive(Debug, Clone)]
 pub struct Error {
     pub span: Span,
     pub message: String,
 }
 
+#[derive(Debug, Clone)]
+pub struct Warning {
+    pub span: Span,
+    pub message: String,
+}
+
+impl Predicate {
+    /// Returns a predicate without a label, a reason or inferred parameters,
+    /// whose left side is sized.
+    pub fn new(left_side: TokenStream, bound: TokenStream, span: Span) -> Self {
+        Self {
+            left_side,
+            bound,
+            span,
+            unsized_left_side: false,
+            label: None,
+            reason: None,
+            inferred_params: Vec::new(),
+        }
+    }
+}
+
+impl PredicateGroup {
+    /// Returns a group without generic parameters, where-predicates or
+    /// attributes.
+    pub fn new(predicates: Vec<PredicateTree>, span: Span) -> Self {
+        Self {
+            generic_params: Vec::new(),
+            where_predicates: Vec::new(),
+            predicates,
+            span,
+            attrs: Vec::new(),
+            unconditional_if_concrete: false,
+        }
+    }
+
+    /// Sets the span of every asserted predicate in the group, including in
+    /// nested groups.
+    pub fn respan_predicates(&mut self, span: Span) {
+        for tree in &mut self.predicates {
+            match tree {
+                PredicateTree::Group(group) => group.respan_predicates(span),
+                PredicateTree::Predicate(predicate) => predicate.span = span,
+            }
+        }
+    }
+}
+
+impl PredicateTree {
+    /// Returns the predicates of the trees with their contexts, in the order
+    /// they are written in.
+    pub fn flatten(trees: &[Self]) -> Vec<ContextPredicate<'_>> {
+        let mut result = Vec::new();
+        flatten_into(
+            trees,
+            &mut Vec::new(),
+            &mut Vec::new(),
+            &mut Vec::new(),
+            &mut result,
+        );
+
+        result
+    }
+}
+
+fn flatten_into<'a>(
+    trees: &'a [PredicateTree],
+    generic_params: &mut Vec<&'a GenericParam>,
+    where_predicates: &mut Vec<&'a Predicate>,
+    attrs: &mut Vec<&'a TokenStream>,
+    result: &mut Vec<ContextPredicate<'a>>,
+) {
+    for tree in trees {
+        match tree {
+            PredicateTree::Group(group) => {
+                let original_lens = (generic_params.len(), where_predicates.len(), attrs.len());
+
+                generic_params.extend(&group.generic_params);
+                where_predicates.extend(&group.where_predicates);
+                attrs.extend(&group.attrs);
+
+                flatten_into(
+                    &group.predicates,
+                    generic_params,
+                    where_predicates,
+                    attrs,
+                    result,
+                );
+
+                generic_params.truncate(original_lens.0);
+                where_predicates.truncate(original_lens.1);
+                attrs.truncate(original_lens.2);
+            }
+            PredicateTree::Predicate(predicate) => result.push(ContextPredicate {
+                generic_params: generic_params.clone(),
+                where_predicates: where_predicates.clone(),
+                attrs: attrs.clone(),
+                predicate,
+            }),
+        }
+    }
+}
+
+impl ContextPredicate<'_> {
+    /// Returns the predicate with its context, like
+    /// `for<T> Vec<T>: Clone where T: Clone`.
+    ///
+    /// Lifetime parameters come first, like in the generated code.
+    pub fn text(&self) -> String {
+        let mut result = String::new();
+
+        if !self.generic_params.is_empty() {
+            let mut generic_params = self.generic_params.clone();
+            generic_params.sort_by(|a, b| a.list_cmp(b));
+
+            let generic_params = generic_params
+                .iter()
+                .map(|p| pretty(&p.to_token_stream()))
+                .collect::<Vec<_>>();
+
+            result.push_str(&format!("for<{}> ", generic_params.join(", ")));
+        }
+
+        result.push_str(&pretty(&self.predicate.to_token_stream()));
+
+        if !self.where_predicates.is_empty() {
+            let where_predicates = self
+                .where_predicates
+                .iter()
+                .map(|p| pretty(&p.to_token_stream()))
+                .collect::<Vec<_>>();
+
+            result.push_str(&format!(" where {}", where_predicates.join(", ")));
+        }
+
+        result
+    }
+}
+
 impl ToTokens for Predicate {
     fn to_tokens(&self, tokens: &mut TokenStream) {
         let tk_colon = Punct::new(':', Spacing::Alone);
@@ -109,6 +387,38 @@ impl ToTokens for Predicate {
 }
 
 impl GenericParam {
+    /// Returns the declaration of a type parameter with an optional bound.
+    pub fn new_type(name: Ident, bound: Option<TokenStream>) -> Self {
+        let mut tokens = quote! { #name };
+        if let Some(bound) = bound {
+            tokens.extend(quote! { : #bound });
+        }
+
+        Self {
+            tokens,
+            inline_arg: quote! { #name },
+            is_lifetime: false,
+            span: name.span(),
+            name,
+            witness: None,
+        }
+    }
+
+    /// Returns the declaration of a lifetime parameter without bounds.
+    pub fn new_lifetime(name: Ident) -> Self {
+        let mut lifetime_prefix = Punct::new('\'', Spacing::Joint);
+        lifetime_prefix.set_span(name.span());
+
+        Self {
+            tokens: quote! { #lifetime_prefix #name },
+            inline_arg: quote! { #lifetime_prefix #name },
+            is_lifetime: true,
+            span: name.span(),
+            name,
+            witness: None,
+        }
+    }
+
     pub fn list_cmp(&self, other: &Self) -> Ordering {
         match (self.is_lifetime, other.is_lifetime) {
             (false, false) | (true, true) => Ordering::Equal,
@@ -116,6 +426,33 @@ impl GenericParam {
             (true, false) => Ordering::Less,
         }
     }
+
+    /// Returns true if `other` declares the same name in the same namespace.
+    pub fn shares_name_with(&self, other: &Self) -> bool {
+        self.is_lifetime == other.is_lifetime && self.name == other.name
+    }
+
+    /// Returns true if `tokens` mention the parameter's name.
+    pub fn is_used_in(&self, tokens: &TokenStream) -> bool {
+        let mut is_after_lifetime_prefix = false;
+
+        for token in tokens.clone() {
+            match &token {
+                TokenTree::Ident(ident)
+                    if *ident == self.name && is_after_lifetime_prefix == self.is_lifetime =>
+                {
+                    return true;
+                }
+                TokenTree::Group(group) if self.is_used_in(&group.stream()) => return true,
+                _ => {}
+            }
+
+            is_after_lifetime_prefix =
+                matches!(&token, TokenTree::Punct(punct) if punct.as_char() == '\'');
+        }
+
+        false
+    }
 }
 
 impl ToTokens for GenericParam {
@@ -139,3 +476,29 @@ impl ToTokens for Error {
         tokens.extend(quote_spanned! { self.span => compile_error!(#message); });
     }
 }
+
+impl Warning {
+    pub fn new_at_span(span: Span, message: impl Into<String>) -> Self {
+        Self {
+            span,
+            message: message.into(),
+        }
+    }
+}
+
+impl ToTokens for Warning {
+    /// Stable Rust has no way for proc-macros to emit warnings, so this uses
+    /// a deprecated item whose deprecation note is the warning message.
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+        let message = &self.message;
+        tokens.extend(quote_spanned! {
+            self.span =>
+            const _: () = {
+                #[deprecated(note = #message)]
+                struct AssertImplWarning;
+
+                let _ = AssertImplWarning;
+            };
+        });
+    }
+}
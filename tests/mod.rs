@@ -24,14 +24,12 @@ assert_impl!(
     for<const N: usize,> {
         [Vec<Vec<u8>>; N]: Into<[Vec<Vec<u8>>; N]>,
 
-        for<const N2: usize,> {
-            [Vec<Vec<u8>>; N2]: Into<[Vec<Vec<u8>>; N2]>,
+        for<const N: usize,> {
+            [Vec<Vec<u8>>; N]: Into<[Vec<Vec<u8>>; N]>,
             [Vec<Vec<u8>>; 3]: Into<[Vec<Vec<u8>>; 3]>,
         }
     },
 
-    for<'a> where &'static Vec<u8>: Copy {}
-
     for<T: Clone> {
         T: Clone
     }
@@ -51,3 +49,668 @@ assert_impl!(
     u8: std::ops::Add,
     u8: std::ops::Add<Output = u8>,
 );
+
+// Operator families expand to owned and borrowed operand combinations.
+assert_impl!(
+    ops(u8, u8): Add + Sub + Mul + AddAssign + SubAssign,
+    ops(core::num::Wrapping<u16>, core::num::Wrapping<u16>): BitAnd + BitXor + BitOrAssign,
+    ops(u64, u32) -> u64: Shl + Shr + ShlAssign,
+    ops(i32): Neg + Not,
+    ops(bool) -> bool: Not,
+
+    for<'a> {
+        &'a i64: Copy,
+        ops(i64, i64): Rem + RemAssign,
+    }
+);
+
+// Lints can be silenced with inner attributes.
+assert_impl!(
+    #![allow(empty_group, unused_generic_param)]
+    #![allow(duplicate_predicate, redundant_where_predicate)]
+
+    for<'a> where &'static Vec<u8>: Copy {}
+
+    u8: Copy,
+    u8: Copy,
+
+    for<T> where T: Clone {
+        where T: Clone {
+            T: Clone,
+        }
+    }
+);
+
+// Parameters that only appear in bounds or where-predicates are used.
+assert_impl!(
+    for<T, U: From<T>> {
+        Option<U>: From<U>,
+    }
+
+    for<T, U> where U: From<T> {
+        Option<U>: From<U>,
+    }
+
+    for<T> {
+        where Vec<T>: Clone {
+            u8: Copy,
+        }
+    }
+);
+
+// Witnesses instantiate the context to prove its where-clause can be met.
+assert_impl!(
+    for<T = u8, const N: usize = 4> where T: Copy {
+        [T; N]: Copy,
+
+        for<U = &'static str> where U: Copy {
+            (T, U): Copy,
+        }
+
+        where T: Default {
+            (T, Option<T>): Clone + Default,
+        }
+    }
+
+    for<T: Clone = Vec<u8>> {
+        Option<T>: Clone,
+    }
+);
+
+// Type lists repeat their body for each type.
+assert_impl!(
+    for T in [u8, &'static str, String, Vec<u8>,] where T: Clone {
+        T: Clone + Send,
+        Option<T>: Clone,
+        <T as ToOwned>::Owned: Clone,
+
+        for<'a> {
+            &'a T: Copy,
+        }
+
+        for U in [u8, T] {
+            (T, U): Clone,
+        }
+    }
+);
+
+// Const ranges and lists repeat their body for each value.
+mod const_values {
+    #![allow(dead_code)]
+
+    use assert_impl_trait::assert_impl;
+
+    struct Bits<const N: u8>;
+    struct Flag<const B: bool>;
+    struct Letter<const C: char>;
+    struct Offset<const I: i8>;
+    struct Wide<const I: i128>;
+
+    trait Byte {}
+    impl<const N: u8> Byte for Bits<N> {}
+
+    impl Clone for Flag<true> {
+        fn clone(&self) -> Self {
+            Self
+        }
+    }
+
+    assert_impl!(
+        for const N in 0..=32 {
+            [u8; N]: Default,
+            [Option<String>; N]: Default,
+        }
+
+        for const N in [0, 1, 2, 4, 8, 0x10, 1_0, 4 * 8, { 4 * 8 }] {
+            [u8; N]: Default + Copy,
+        }
+
+        for const N in 0u8..4 {
+            Bits<N>: Byte,
+        }
+
+        for const B in true..=true where Flag<B>: Sized {
+            Flag<B>: Clone,
+        }
+
+        for const B in [false, true] {
+            Flag<B>: Send,
+        }
+
+        for const C in 'a'..='z' {
+            Letter<C>: Sync,
+        }
+
+        for const I in -3..=3 {
+            Offset<I>: Send,
+        }
+
+        for const I in 170141183460469231731687303715884105725..170141183460469231731687303715884105727 {
+            Wide<I>: Send,
+        }
+    );
+}
+
+// Variadic parameters repeat their group for each arity.
+assert_impl!(
+    for<..Ts: Clone; 0..=12> {
+        (..Ts,): Clone,
+        (u8, ..Ts,): Clone,
+    }
+
+    for<..Ts: Copy + Default; 0..=12> {
+        (..Ts,): Copy + Default,
+    }
+
+    for<'a, R, ..Ts; 0..=12> where R: 'a {
+        fn(..Ts) -> R: Copy,
+        fn(..Ts,): Copy,
+        Box<dyn Fn(..Ts) -> R + 'a>: Sized,
+    }
+
+    for<..Ts; [1, 3]> {
+        (u8, ..Ts): Sized,
+    }
+);
+
+// Comparison matrices assert both directions of every pair of types.
+assert_impl!(
+    compare!(String, &str, str, std::borrow::Cow<'_, str>; except(&str == str, str == &str)),
+    compare!(std::net::IpAddr, std::net::Ipv4Addr; ord),
+    compare!(std::net::IpAddr, std::net::Ipv6Addr; ord),
+    compare!(&std::ffi::OsStr, std::ffi::OsString, std::path::PathBuf, &std::path::Path; ord),
+);
+
+// Conversion chains assert `From` forward and `TryFrom` backward.
+assert_impl!(
+    convert!(u8 -> u16 -> u32 -> u64, u8 -> i16 -> i32; transitive),
+    convert!(&str -> String -> Box<str>, std::io::ErrorKind -> std::io::Error; forward_only),
+    convert!(char -> u32),
+);
+
+// Forwarding impls are asserted for the usual wrappers.
+mod forwarding {
+    #![allow(dead_code)]
+
+    use assert_impl_trait::assert_impl;
+
+    trait Shape {
+        fn area(&self) -> f64;
+    }
+
+    impl<T: Shape + ?Sized> Shape for &T {
+        fn area(&self) -> f64 {
+            (**self).area()
+        }
+    }
+
+    impl<T: Shape + ?Sized> Shape for &mut T {
+        fn area(&self) -> f64 {
+            (**self).area()
+        }
+    }
+
+    impl<T: Shape + ?Sized> Shape for Box<T> {
+        fn area(&self) -> f64 {
+            (**self).area()
+        }
+    }
+
+    impl<T: Shape + ?Sized> Shape for std::rc::Rc<T> {
+        fn area(&self) -> f64 {
+            (**self).area()
+        }
+    }
+
+    impl<T: Shape + ?Sized> Shape for std::sync::Arc<T> {
+        fn area(&self) -> f64 {
+            (**self).area()
+        }
+    }
+
+    assert_impl!(
+        for<T: Shape + ?Sized> {
+            fwd(T): Shape,
+        }
+
+        fwd(dyn Shape; Box<dyn>, &, Arc): Shape,
+        fwd(str; &, Box, Rc, Arc): core::fmt::Display,
+        fwd(u8; &, &mut, Box<dyn>): core::fmt::Debug,
+    );
+}
+
+// Collection presets assert the std collection traits.
+mod collections {
+    #![allow(dead_code)]
+
+    use assert_impl_trait::assert_impl;
+
+    struct NoTraits;
+
+    #[derive(Clone)]
+    struct Names;
+
+    // The assertions that depend on the traits of a concrete item type are
+    // made directly, and can be skipped.
+    assert_impl!(
+        for<T> {
+            collection!(Vec<T>, item = T, index = usize),
+            collection!(std::collections::VecDeque<T>, item = T, index = usize),
+        }
+
+        collection!(std::collections::BTreeSet<String>, item = String, skip(into_iter_mut, extend_ref)),
+        collection!(String, item = char, skip(into_iter, into_iter_ref, into_iter_mut)),
+        collection!(Vec<String>, item = String, index = usize, skip(extend_ref)),
+        collection!(Vec<NoTraits>, item = NoTraits, skip(extend_ref, debug, clone, partial_eq)),
+        collection!(
+            Names,
+            item = String,
+            skip(into_iter, into_iter_ref, into_iter_mut, from_iter, extend, extend_ref, default, debug, partial_eq),
+        ),
+    );
+}
+
+// The API Guidelines preset labels its assertions with the guidelines.
+mod api_guidelines {
+    #![allow(dead_code)]
+
+    use assert_impl_trait::assert_impl;
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Hash, Default)]
+    struct Meters(u32);
+
+    #[derive(Debug)]
+    struct ParseError;
+
+    impl std::fmt::Display for ParseError {
+        fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+            f.write_str("invalid input")
+        }
+    }
+
+    impl std::error::Error for ParseError {}
+
+    assert_impl!(
+        api_guidelines!(Meters),
+        api_guidelines!(ParseError; skip(clone, eq, partial_ord, hash, default); with(error)),
+        api_guidelines!(std::io::Error; skip(clone, eq, partial_ord, hash, default); with(error)),
+
+        for<T: core::fmt::Debug + Clone + Eq + PartialOrd + core::hash::Hash + Send + Sync> {
+            api_guidelines!(Vec<T>),
+        }
+    );
+}
+
+// Templates generate declarative macros that repeat their assertions.
+mod templates {
+    use assert_impl_trait::assert_template;
+
+    assert_template!(
+        /// Asserts that each type is a numeric type.
+        numeric<T> {
+            T: Copy + Default + core::ops::Add<Output = T>,
+            [T; 4]: Default,
+
+            for<'a> where T: 'a {
+                &'a T: core::ops::Add<T, Output = T>,
+            }
+        }
+    );
+
+    assert_template!(
+        pub widening<Small, Large> {
+            #![allow(duplicate_predicate)]
+
+            Large: From<Small>,
+            Small: TryFrom<Large>,
+        }
+    );
+
+    // Inner groups that declare a parameter with the name of a template
+    // parameter shadow it.
+    assert_template!(
+        shadowing<T> {
+            T: Copy,
+
+            for<T: Clone> {
+                Vec<T>: Clone,
+            }
+            for<const N: usize, T: Copy> where [T; N]: Copy {
+                [T; N]: Clone,
+            }
+            impl<T: Clone> Clone for Box<T> {}
+            for T in [u8, char] {
+                T: Copy,
+            }
+        }
+    );
+
+    numeric!(u8, i32, f64,);
+    numeric!(core::num::Wrapping<u16>);
+    widening!([u8, u16], [u16, u32], [char, u32]);
+    shadowing!(u16, bool);
+}
+
+// Trait relations assert that every implementor of one side implements the
+// other side.
+assert_impl!(
+    trait Copy => Clone,
+    trait Eq <=> Eq + PartialEq,
+    trait core::fmt::Display => ToString,
+    trait Ord => PartialOrd + Eq,
+    trait Iterator + Sized => IntoIterator,
+    trait Fn() -> u8 => FnMut() -> u8,
+
+    for<T> trait PartialOrd<T> => PartialEq<T>,
+    for<T: ?Sized> {
+        trait AsRef<T> <=> AsRef<T>,
+    }
+);
+
+// Impl headers can be pasted from rustdoc.
+assert_impl!(
+    impl Clone for u8,
+    impl<T: Clone> Clone for Vec<T>,
+    impl<T> Clone for Box<[T]> where T: Clone;
+    impl<T: ?Sized + core::fmt::Display> ToString for T {}
+    impl<'a, T, const N: usize> IntoIterator for &'a [T; N]
+    where
+        T: 'a;
+    unsafe impl Send for String,
+    impl<K: Ord, V> FromIterator<(K, V)> for std::collections::BTreeMap<K, V>,
+    impl dyn core::fmt::Debug,
+    impl<T> dyn AsRef<T>,
+    impl<..Ts: Clone; 0..=4> Clone for (..Ts,),
+    impl<T: Clone, const N: usize> Clone for core::array::IntoIter<T, { N }>
+    where
+        core::array::IntoIter<T, { N }>: Iterator {}
+);
+
+// Placeholders are inferred by the compiler.
+assert_impl!(
+    Vec<_>: FromIterator<u8>,
+    std::vec::IntoIter<u8>: Iterator<Item = _>,
+    Option<_>: From<u8>,
+    std::collections::HashMap<_, _>: FromIterator<(u8, String)>,
+
+    for<'a, T: 'a + Clone, const N: usize> {
+        Vec<_>: FromIterator<T>,
+        core::slice::Iter<'a, [T; N]>: Iterator<Item = &'a _>,
+    }
+);
+
+// The predicates of a context are checked together, once each, and their left
+// sides can use elided lifetimes and unsized types like any generic argument.
+assert_impl!(
+    #![allow(duplicate_predicate)]
+
+    &str: Copy,
+    &str: Copy,
+    std::borrow::Cow<'_, str>: Clone,
+    str: ?Sized + Send,
+    dyn core::fmt::Debug:,
+);
+
+// Predicates and groups can be guarded by `cfg` attributes.
+assert_impl!(
+    #[cfg(any())]
+    u8: core::ops::Neg,
+    #[cfg(target_has_atomic = "64")]
+    core::sync::atomic::AtomicU64: Send + Sync,
+    #[cfg_attr(all(), allow(unused))]
+    u8: Copy,
+
+    #[cfg(all())]
+    for<T: Clone> {
+        #[cfg(any())]
+        T: Copy,
+        Vec<T>: Clone,
+    }
+
+    #[cfg(any())]
+    #[cfg(all())]
+    for T in [u8, u16] {
+        T: core::ops::Neg,
+    }
+);
+
+// Labels and reasons are shown in errors.
+assert_impl!(
+    #[reason = "values are sent to worker threads"]
+    String: Send + 'static,
+    copyable: u8: Copy,
+    #[reason = "nested"]
+    labelled_group: for<T: Clone> {
+        Vec<T>: Clone,
+        inner: T: Clone,
+        Vec<_>: FromIterator<T>,
+    }
+
+    #[reason = "grouped"]
+    for<'a, T: 'a + Sync, F: Fn(&u8)> {
+        &'a T: Send,
+        F: for<'b> Fn(&'b u8),
+    }
+
+    #[reason = "placeholders are inferred"]
+    std::vec::IntoIter<u8>: Iterator<Item = _>,
+    for<'a, T: 'a> {
+        #[reason = "placeholders are inferred in generic contexts too"]
+        core::slice::Iter<'a, T>: Iterator<Item = &'a _>,
+    }
+);
+
+// With `#![test]`, each top-level tree is also listed as a test.
+mod listed_tests {
+    use assert_impl_trait::assert_impl;
+
+    assert_impl!(
+        #![test]
+
+        u8: Copy,
+        Vec<u8>: Clone,
+        copyable: for<T: Copy> {
+            Option<T>: Copy,
+        }
+        #[cfg(any())]
+        u8: core::ops::Neg,
+        {unsigned}: Default,
+    );
+
+    // Invocations with the same predicates put their tests in modules.
+    assert_impl!(#![test(mod first)] u8: Copy);
+    assert_impl!(#![test(mod second)] u8: Copy);
+
+    // Each instance of a template gets its own module.
+    assert_impl_trait::assert_template!(
+        listed<T> {
+            #![test(mod listed<T>)]
+
+            Vec<u8>: Clone,
+            T: Copy,
+        }
+    );
+
+    listed!(u8, u16, Option<u8>);
+}
+
+// `#![descriptors(...)]` lists the asserted predicates at runtime.
+mod descriptors {
+    use assert_impl_trait::assert_impl;
+
+    assert_impl!(
+        #![descriptors(pub(crate) mod guarantees)]
+
+        u8: Copy,
+        copyable: for<T: Copy> where T: Default {
+            Option<T>: Copy,
+        }
+        #[cfg(any())]
+        u8: core::ops::Neg,
+        #[cfg_attr(all(), allow(unused))]
+        u16: Copy,
+        &'static [u8]: IntoIterator<Item = &'static u8> + ?Sized,
+        <u8 as core::ops::Add>::Output: Copy,
+    );
+
+    #[test]
+    fn lists_predicates() {
+        let descriptors = guarantees::DESCRIPTORS;
+        assert_eq!(descriptors.len(), 5);
+
+        assert_eq!(descriptors[0].predicate, "u8: Copy");
+        assert_eq!(descriptors[0].label, None);
+        assert_eq!(descriptors[0].file, file!());
+
+        assert_eq!(descriptors[1].predicate, "Option<T>: Copy");
+        assert_eq!(descriptors[1].generic_params, ["T: Copy"]);
+        assert_eq!(descriptors[1].where_predicates, ["T: Default"]);
+        assert_eq!(descriptors[1].label, Some("copyable"));
+        assert_eq!(descriptors[1].line, descriptors[0].line + 2);
+
+        assert_eq!(
+            descriptors[3].predicate,
+            "&'static [u8]: IntoIterator<Item = &'static u8> + ?Sized",
+        );
+        assert_eq!(
+            descriptors[4].predicate,
+            "<u8 as core::ops::Add>::Output: Copy"
+        );
+    }
+}
+
+// Asserted predicates can be listed in the documentation of an item.
+mod docs {
+    #![allow(dead_code)]
+
+    use assert_impl_trait::{assert_impl, guarantees};
+
+    /// A list of values.
+    #[guarantees(
+        for<T: Clone> {
+            List<T>: Clone,
+        }
+        #[cfg(any())]
+        List<u8>: Copy,
+    )]
+    #[derive(Clone)]
+    pub struct List<T>(Vec<T>);
+
+    assert_impl!(
+        #![docs(pair_docs)]
+
+        for<T> where T: Copy {
+            Pair<T>: Copy,
+        }
+        #[cfg(target_has_atomic = "64")]
+        Pair<core::sync::atomic::AtomicU64>: Send,
+    );
+
+    #[doc = pair_docs!()]
+    #[derive(Clone, Copy)]
+    pub struct Pair<T>(T, T);
+
+    #[test]
+    fn lists_predicates() {
+        let docs = pair_docs!();
+
+        assert!(docs.contains("# Guaranteed implementations"));
+        assert!(docs.contains("- `for<T> Pair<T>: Copy where T: Copy`\n"));
+        assert!(docs.contains(
+            "- `Pair<core::sync::atomic::AtomicU64>: Send` \
+            (only with `target_has_atomic = \"64\"`)\n"
+        ));
+    }
+}
+
+// `#![explain]` shows the flattened predicates as a warning.
+mod explain {
+    #![allow(deprecated)]
+
+    use assert_impl_trait::assert_impl;
+
+    assert_impl!(
+        #![explain]
+
+        u8: Copy,
+        for<T: Clone> where T: Default {
+            #[cfg(all())]
+            for<'a, T: 'a + Copy> {
+                &'a T: Copy,
+            }
+        }
+    );
+
+    assert_impl!(
+        #![explain(tokens)]
+
+        Vec<_>: FromIterator<u8>,
+    );
+}
+
+// Built-in type sets expand to their types, and atomics that don't exist on
+// the target are skipped.
+assert_impl!(
+    {unsigned}: Copy + Default + From<u8>,
+    {signed, floats}: core::ops::Neg,
+    {atomics}: Send + Sync + Default,
+    {primitives}: Copy + PartialOrd,
+
+    for T in {integers} {
+        T: TryFrom<i128> + core::hash::Hash,
+    }
+
+    for T in [{floats}, u8, {atomics}] {
+        T: core::fmt::Debug,
+    }
+);
+
+// Inner generic parameters shadow outer ones with the same name.
+assert_impl!(
+    for<'a, T: 'a + Copy> {
+        &'a T: Copy,
+
+        for<'a, T: 'a + Clone> where T: Default {
+            &'a T: Clone,
+            T: Default,
+
+            for<T: Clone> {
+                Vec<T>: Clone,
+            }
+        }
+
+        T: Copy,
+    }
+);
+
+// The names used by the generated code must not collide with user names.
+mod hygiene {
+    #![allow(non_camel_case_types)]
+
+    use assert_impl_trait::assert_impl;
+
+    struct _HelperTy;
+
+    assert_impl!(
+        for<_HelperTy: Clone, _AssertTy: Clone, _AssertTy0: Clone, _context: Clone> {
+            (_HelperTy, _AssertTy, _AssertTy0, _context): Clone,
+        }
+
+        for<_HelperTy_: Clone> {
+            _HelperTy_: Clone,
+        }
+
+        for<_check: Clone> {
+            _check: Clone,
+        }
+
+        for<'a, _Reason: 'a + Clone, _Inferred0: Clone> {
+            #[reason = "tuples of clonable types are clonable"]
+            (_Reason, _Inferred0): Clone,
+            Vec<_>: FromIterator<_Inferred0>,
+            core::slice::Iter<'a, _Reason>: Iterator<Item = &'a _>,
+        }
+    );
+
+    assert_impl!(_HelperTy: Sized);
+}